@@ -0,0 +1,122 @@
+//! For a producer and consumer that would rather wait than poll, a ring buffer can be wrapped in a mutex, with one
+//! condition variable to wake up a consumer that is waiting for data, and another one to wake up a producer that is
+//! waiting for room. Unlike the halves in `spsc`, this can be shared between any number of producers and consumers.
+
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use crate::RingBuffer;
+
+/// A ring buffer that blocks instead of failing when writing to it while full, or reading from it while empty. It is
+/// meant to be shared between threads, e.g. in an Arc.
+pub struct BlockingRingBuffer {
+    queue: Mutex<RingBuffer>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl BlockingRingBuffer {
+    /// Creates an empty blocking ring buffer backed by an array of `size` bytes, like `RingBuffer::new`
+    pub fn new(size: usize) -> BlockingRingBuffer {
+        BlockingRingBuffer::from(RingBuffer::new(size))
+    }
+
+    /// This function puts `value` on the queue, waiting for room first if the queue is full
+    pub fn push(&self, value: u8) {
+        let queue = self.queue.lock().unwrap();
+        let mut queue = self
+            .not_full
+            .wait_while(queue, |queue| !queue.has_room())
+            .unwrap();
+        queue.write(value);
+        self.not_empty.notify_one();
+    }
+
+    /// This function removes the first value from the queue and returns it, waiting for it first if the queue is
+    /// empty
+    pub fn pop(&self) -> u8 {
+        let queue = self.queue.lock().unwrap();
+        let mut queue = self
+            .not_empty
+            .wait_while(queue, |queue| queue.is_empty())
+            .unwrap();
+        let value = queue.read().unwrap();
+        self.not_full.notify_one();
+        value
+    }
+
+    /// Like `push`, but gives up if there is still no room after `timeout`; returns true if `value` was written
+    pub fn push_timeout(&self, value: u8, timeout: Duration) -> bool {
+        let queue = self.queue.lock().unwrap();
+        let (mut queue, _) = self
+            .not_full
+            .wait_timeout_while(queue, timeout, |queue| !queue.has_room())
+            .unwrap();
+        // there may have been room just in time, even if the wait timed out
+        if !queue.write(value) {
+            return false;
+        }
+        self.not_empty.notify_one();
+        true
+    }
+
+    /// Like `pop`, but gives up if the queue is still empty after `timeout`, returning None
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<u8> {
+        let queue = self.queue.lock().unwrap();
+        let (mut queue, _) = self
+            .not_empty
+            .wait_timeout_while(queue, timeout, |queue| queue.is_empty())
+            .unwrap();
+        let value = queue.read()?;
+        self.not_full.notify_one();
+        Some(value)
+    }
+}
+
+/// Anything that was already queued can be popped right away
+impl From<RingBuffer> for BlockingRingBuffer {
+    fn from(queue: RingBuffer) -> BlockingRingBuffer {
+        BlockingRingBuffer {
+            queue: Mutex::new(queue),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_consumer_waits_for_producer() {
+        let queue = Arc::new(BlockingRingBuffer::new(4));
+
+        let consumer = {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || (0..100).map(|_| queue.pop()).collect::<Vec<_>>())
+        };
+        // give the consumer time to start waiting
+        thread::sleep(Duration::from_millis(50));
+        let producer = {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || (0..100).for_each(|value| queue.push(value)))
+        };
+
+        producer.join().unwrap();
+        assert_eq!(consumer.join().unwrap(), (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let queue = BlockingRingBuffer::from(RingBuffer::from_slice(&[1, 2]));
+        assert!(!queue.push_timeout(3, Duration::from_millis(10)));
+        assert_eq!(queue.pop_timeout(Duration::from_millis(10)), Some(1));
+        assert!(queue.push_timeout(3, Duration::from_millis(10)));
+        assert_eq!(queue.pop(), 2);
+        assert_eq!(queue.pop(), 3);
+        assert_eq!(queue.pop_timeout(Duration::from_millis(10)), None);
+    }
+}
@@ -0,0 +1,109 @@
+//! With the `bytes` feature, a ring buffer can be used as a `bytes::Buf` (to read from) and a `bytes::BufMut` (to
+//! write to), so it can be handed directly to code from the `bytes` ecosystem, such as codecs. In both cases the data
+//! is exposed in place, one contiguous run at a time: e.g. the part of the queue before the wraparound point, and then
+//! the part after it.
+
+use bytes::buf::UninitSlice;
+use bytes::{Buf, BufMut};
+
+use crate::RingBuffer;
+
+impl Buf for RingBuffer {
+    fn remaining(&self) -> usize {
+        self.len()
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.as_slices().0
+    }
+
+    fn advance(&mut self, cnt: usize) {
+        assert!(
+            cnt <= self.len(),
+            "cannot advance past the end of the queue"
+        );
+        self.start = (self.start + cnt) % self.data.len();
+    }
+}
+
+// SAFETY: `chunk_mut` only ever exposes free slots, and `advance_mut` never moves `end` past the free slots (so in
+// particular `remaining_mut` is exact); `data` is always initialized anyway, since it was allocated filled with zeros
+unsafe impl BufMut for RingBuffer {
+    fn remaining_mut(&self) -> usize {
+        self.available_write()
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        assert!(
+            cnt <= self.available_write(),
+            "cannot advance past the free space in the queue"
+        );
+        self.end = (self.end + cnt) % self.data.len();
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        // the free slots run from `end` up to the slot before `start`, wrapping around the end of `data`; this is the
+        // first run of them, which is empty only if the queue is full
+        let stop = if self.start > self.end {
+            self.start - 1
+        } else if self.start == 0 {
+            self.data.len() - 1
+        } else {
+            self.data.len()
+        };
+        UninitSlice::new(&mut self.data[self.end..stop])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_buf_advance_across_wrap() {
+        let mut queue = RingBuffer::new(6);
+        assert_eq!(queue.extend([0, 0, 0, 1, 2]), 5);
+        queue.advance(3);
+        assert_eq!(queue.extend([3, 4, 5]), 3);
+        assert_eq!(queue.remaining(), 5);
+        assert_eq!(queue.chunk(), &[1, 2, 3]);
+
+        // this moves past the wraparound point
+        queue.advance(4);
+        assert_eq!(queue.remaining(), 1);
+        assert_eq!(queue.chunk(), &[5]);
+        assert_eq!(queue.get_u8(), 5);
+        assert!(!queue.has_remaining());
+    }
+
+    #[test]
+    fn test_buf_copy_to_slice() {
+        let mut queue = RingBuffer::new(4);
+        assert_eq!(queue.extend([9, 9, 1]), 3);
+        queue.advance(2);
+        assert_eq!(queue.extend([2, 3]), 2);
+        let mut dst = [0; 3];
+        queue.copy_to_slice(&mut dst);
+        assert_eq!(dst, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_buf_mut_put_slice() {
+        let mut queue = RingBuffer::new(6);
+        assert_eq!(queue.extend([9, 9, 9]), 3);
+        queue.advance(3);
+        assert_eq!(queue.remaining_mut(), 5);
+        // this wraps around the end of the array, and fills the queue
+        queue.put_slice(&[1, 2, 3, 4, 5]);
+        assert_eq!(queue.remaining_mut(), 0);
+        assert!(!queue.has_room());
+        assert_eq!(queue.collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_buf_mut_overflow() {
+        let mut queue = RingBuffer::new(4);
+        queue.put_slice(&[1, 2, 3, 4]);
+    }
+}
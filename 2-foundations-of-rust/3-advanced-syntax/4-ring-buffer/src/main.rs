@@ -1,6 +1,6 @@
-/// One way to implement a queue is to use a linked list; however, that requires a lot of dynamic memory manipulation to add/remove individual items.
-/// A more low-level approach is to use a circular buffer: the compromise is that the capacity of the queue is then "fixed". For a background on circular buffers,
-/// you can consult https://en.wikipedia.org/wiki/Circular_buffer
+//! One way to implement a queue is to use a linked list; however, that requires a lot of dynamic memory manipulation to add/remove individual items.
+//! A more low-level approach is to use a circular buffer: the compromise is that the capacity of the queue is then "fixed". For a background on circular buffers,
+//! you can consult https://en.wikipedia.org/wiki/Circular_buffer
 
 // A partial implementation is provided below; please finish it and add some more methods; please remember to run 'cargo fmt' and 'cargo clippy' after
 // every step to get feedback from the rust compiler!
@@ -18,54 +18,437 @@
 //  - add a method "has_room" so that "queue.has_room()" is true if and only if writing to the queue will succeed
 //  - add a method "peek" so that "queue.peek()" returns the same thing as "queue.read()", but leaves the element in the queue
 
+use std::collections::VecDeque;
+
+mod blocking;
+#[cfg(feature = "bytes")]
+mod buf;
+mod spsc;
+
+// The queued elements are `data[start..end]`, wrapping around the end of `data`. So `start == end` means the queue is
+// empty, and we can't allow `end` to catch up with `start` when writing: a queue with `data.len() == N` holds at most
+// N - 1 elements, and the slot at `end` is always unused.
+//
+// A clone gets its own copy of `data`, so it can be read from or written to without affecting the original.
+#[derive(Clone)]
 struct RingBuffer {
-    data: [u8; 16],
+    data: Box<[u8]>,
     start: usize,
     end: usize,
+    // the number of times `write` returned false since the queue was created or last cleared
+    dropped_writes: u64,
 }
 
 impl RingBuffer {
-    fn new() -> RingBuffer {
+    /// Creates an empty ring buffer backed by an array of `size` bytes. One slot is always kept free to tell a full
+    /// queue apart from an empty one, so the buffer can hold `size - 1` elements at a time.
+    fn new(size: usize) -> RingBuffer {
+        assert!(
+            size > 0,
+            "a ring buffer needs room for at least its reserved slot"
+        );
         RingBuffer {
-            data: [0; 16],
+            data: make_box(size),
             start: 0,
             end: 0,
+            dropped_writes: 0,
+        }
+    }
+
+    /// Creates an empty ring buffer that can hold `cap` elements at a time, i.e. with `capacity() == cap`; because of
+    /// the reserved slot, it is backed by an array of `cap + 1` bytes (so this is the same as `new(cap + 1)`)
+    fn with_usable_capacity(cap: usize) -> RingBuffer {
+        RingBuffer::new(cap + 1)
+    }
+
+    /// Creates a ring buffer that just fits `src` (so of size `src.len() + 1`), with all of `src` already queued
+    fn from_slice(src: &[u8]) -> RingBuffer {
+        let mut queue = RingBuffer::new(src.len() + 1);
+        for &value in src {
+            queue.write(value);
         }
+        queue
+    }
+
+    /// Creates a ring buffer backed by an array of `size` bytes, with all of `initial` already queued; this fails if
+    /// `initial` doesn't fit, i.e. if it is longer than `size - 1`
+    fn with_data(size: usize, initial: &[u8]) -> Result<RingBuffer, ()> {
+        if initial.len() >= size {
+            return Err(());
+        }
+        let mut queue = RingBuffer::new(size);
+        queue.write_slice(initial);
+        Ok(queue)
     }
 
     /// This function tries to read a value from the queue and returns Some(value) if this succeeds,
     /// it returns None if the queue was empty
-
     fn read(&mut self) -> Option<u8> {
-        todo!()
+        let value = self.peek()?;
+        self.start = (self.start + 1) % self.data.len();
+        Some(value)
+    }
+
+    /// This function returns the value that `read` would return, but leaves it on the queue
+    fn peek(&self) -> Option<u8> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.data[self.start])
+        }
+    }
+
+    /// This function returns the element `offset` places from the front of the queue (so `peek_at(0)` is `peek()`),
+    /// without removing anything; it returns None if fewer than `offset + 1` elements are queued
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        if offset < self.len() {
+            Some(self.data[(self.start + offset) % self.data.len()])
+        } else {
+            None
+        }
+    }
+
+    /// This function returns the first two elements on the queue without removing them, or None if fewer than two
+    /// are queued
+    fn peek2(&self) -> Option<(u8, u8)> {
+        Some((self.peek_at(0)?, self.peek_at(1)?))
+    }
+
+    /// This function moves up to `dst.len()` elements from the queue into `dst`, in read order, and returns how many
+    /// were moved
+    fn read_slice(&mut self, dst: &mut [u8]) -> usize {
+        let count = self.peek_slice(dst);
+        self.start = (self.start + count) % self.data.len();
+        count
+    }
+
+    /// This function removes exactly `n` elements from the queue and returns them in read order, if there are that
+    /// many; unlike `read_slice` it removes nothing at all otherwise, returning None
+    fn read_frame(&mut self, n: usize) -> Option<Vec<u8>> {
+        if n > self.len() {
+            return None;
+        }
+        let mut frame = vec![0; n];
+        self.read_slice(&mut frame);
+        Some(frame)
+    }
+
+    /// This function fills all of `dst` with elements from the queue if there are enough of them; unlike `read_slice`
+    /// it removes nothing at all otherwise, returning Err with the number of elements that could have been read
+    fn read_exact_into(&mut self, dst: &mut [u8]) -> Result<(), usize> {
+        if dst.len() > self.len() {
+            return Err(self.len());
+        }
+        self.read_slice(dst);
+        Ok(())
+    }
+
+    /// This function copies up to `dst.len()` elements from the front of the queue into `dst`, like `read_slice`,
+    /// but leaves them on the queue
+    fn peek_slice(&self, dst: &mut [u8]) -> usize {
+        let count = dst.len().min(self.len());
+        // the queued elements may be split in two runs: from `start` up to the end of `data`, and from index 0 onward
+        let first = count.min(self.data.len() - self.start);
+        dst[..first].copy_from_slice(&self.data[self.start..self.start + first]);
+        dst[first..count].copy_from_slice(&self.data[..count - first]);
+        count
     }
 
     /// This function tries to put `value` on the queue; and returns true if this succeeds
     /// It returns false if writing to the queue failed (which can happen if there is not enough room)
-
     fn write(&mut self, value: u8) -> bool {
+        if !self.has_room() {
+            // the buffer can hold no more new data; note that we must not even touch `data[end]` in this case
+            self.dropped_writes += 1;
+            return false;
+        }
         self.data[self.end] = value;
-        let pos = (self.end + 1) % self.data.len();
-        if pos == self.start {
-            // the buffer can hold no more new data
-            false
+        self.end = (self.end + 1) % self.data.len();
+
+        true
+    }
+
+    /// This function puts as many elements from the front of `src` on the queue as there is room for, and returns how
+    /// many were written
+    fn write_slice(&mut self, src: &[u8]) -> usize {
+        let count = src.len().min(self.available_write());
+        // the free slots may be split in two runs: from `end` up to the end of `data`, and from index 0 onward
+        let first = count.min(self.data.len() - self.end);
+        self.data[self.end..self.end + first].copy_from_slice(&src[..first]);
+        self.data[..count - first].copy_from_slice(&src[first..count]);
+        self.end = (self.end + count) % self.data.len();
+        count
+    }
+
+    /// This function puts `value` on the queue even if it is full, by first removing the oldest element; it returns
+    /// the removed element, if any. (A queue with a capacity of 0 can't hold `value` at all, so it is returned as is.)
+    fn write_overwrite(&mut self, value: u8) -> Option<u8> {
+        if self.capacity() == 0 {
+            return Some(value);
+        }
+        let dropped = if self.has_room() { None } else { self.read() };
+        self.write(value);
+        dropped
+    }
+
+    /// This function puts all of `src` on the queue, removing as many of the oldest elements as needed to make room;
+    /// if `src` is longer than the capacity of the queue, only its last `capacity()` elements are kept
+    fn write_slice_overwrite(&mut self, src: &[u8]) {
+        let src = &src[src.len().saturating_sub(self.capacity())..];
+        let dropped = src.len().saturating_sub(self.available_write());
+        self.start = (self.start + dropped) % self.data.len();
+        self.write_slice(src);
+    }
+
+    /// This function puts all of `src` on the queue if there is room for it; unlike `write_slice` it writes nothing
+    /// at all otherwise, returning Err with the number of elements that could have been written
+    fn write_all(&mut self, src: &[u8]) -> Result<(), usize> {
+        if src.len() > self.available_write() {
+            return Err(self.available_write());
+        }
+        self.write_slice(src);
+        Ok(())
+    }
+
+    /// This function writes the elements produced by `iter` until the queue is full, and returns how many were
+    /// written; nothing already on the queue is overwritten, and `iter` is not advanced past the last written element
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, iter: I) -> usize {
+        let mut written = 0;
+        let mut iter = iter.into_iter();
+        while self.has_room() {
+            match iter.next() {
+                Some(value) => {
+                    self.write(value);
+                    written += 1;
+                }
+                None => break,
+            }
+        }
+        written
+    }
+
+    /// This function writes copies of `value` until the queue is full, and returns how many were written
+    fn fill(&mut self, value: u8) -> usize {
+        self.extend(std::iter::repeat(value))
+    }
+
+    /// This function removes the queued elements for which `f` returns true, and returns them in read order; the
+    /// other elements stay on the queue, in the same order
+    fn drain_filter<F: FnMut(u8) -> bool>(&mut self, mut f: F) -> Vec<u8> {
+        let mut drained = Vec::new();
+        // every element is read once and, unless it is drained, put back at the end; the read makes room for that
+        for _ in 0..self.len() {
+            let value = self.read().unwrap();
+            if f(value) {
+                drained.push(value);
+            } else {
+                self.write(value);
+            }
+        }
+        drained
+    }
+
+    /// Returns true if and only if the next call to `write` will succeed
+    fn has_room(&self) -> bool {
+        (self.end + 1) % self.data.len() != self.start
+    }
+
+    /// Returns the number of elements currently on the queue
+    fn len(&self) -> usize {
+        (self.end + self.data.len() - self.start) % self.data.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns the number of elements that can still be written before the queue is full
+    fn available_write(&self) -> usize {
+        self.capacity() - self.len()
+    }
+
+    /// Returns the maximum number of elements the queue can hold at one time; this is one less than the size of the
+    /// underlying array, since one slot is reserved to distinguish a full queue from an empty one
+    fn capacity(&self) -> usize {
+        self.data.len() - 1
+    }
+
+    /// Returns the internal bookkeeping `(start, end)`: the index in the underlying array of the next element to read,
+    /// and of the next free slot to write. This exposes the raw layout, e.g. for zero-copy protocols; the queue is
+    /// empty when they are equal, and wraps around when `start > end`.
+    fn positions(&self) -> (usize, usize) {
+        (self.start, self.end)
+    }
+
+    /// Returns the size of the underlying array, which includes the reserved slot (so it is one more than `capacity`)
+    fn data_len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns how many times `write` failed because the queue was full, since it was created or last cleared; a
+    /// producer can poll this to notice that the consumer isn't keeping up. Only `write` is counted: the functions
+    /// that write several elements at once already report how many of them were written.
+    fn dropped_writes(&self) -> u64 {
+        self.dropped_writes
+    }
+
+    /// Empties the queue; this only resets the bookkeeping (including `dropped_writes`), so the old elements are still
+    /// present in `data`
+    fn clear(&mut self) {
+        self.start = 0;
+        self.end = 0;
+        self.dropped_writes = 0;
+    }
+
+    /// Empties the queue like `clear`, but also overwrites all of `data` with zeros, so no old elements linger in
+    /// memory (which matters if they were sensitive)
+    fn reset(&mut self) {
+        self.data.fill(0);
+        self.clear();
+    }
+
+    /// Moves the queue into a freshly allocated array of `new_size` bytes, keeping the queued elements in read order.
+    /// The elements are stored starting at index 0 of the new array.
+    ///
+    /// This never drops data: if the queued elements do not fit in a buffer of `new_size` (i.e. there are more than
+    /// `new_size - 1` of them), the buffer is left untouched and false is returned.
+    fn resize(&mut self, new_size: usize) -> bool {
+        let len = self.len();
+        if new_size == 0 || len > new_size - 1 {
+            return false;
+        }
+
+        let mut data = make_box(new_size);
+        for (slot, value) in data.iter_mut().zip(self.iter()) {
+            *slot = value;
+        }
+        self.data = data;
+        self.start = 0;
+        self.end = len;
+
+        true
+    }
+
+    /// Moves the queued elements to the front of `data` (keeping them in read order), so they are stored in a single
+    /// run; afterwards `start` is 0, and the second slice returned by `as_slices` is empty
+    fn compact(&mut self) {
+        let len = self.len();
+        // this moves `data[start]` to index 0, followed by the rest of the queue (including any part that wrapped
+        // around)
+        self.data.rotate_left(self.start);
+        self.start = 0;
+        self.end = len;
+    }
+
+    /// Compacts the queue like `compact`, and returns its elements as a single slice; they stay on the queue
+    fn as_contiguous(&mut self) -> &[u8] {
+        self.compact();
+        &self.data[..self.end]
+    }
+
+    /// Returns the queued elements as two slices, which together hold them in read order: the first one runs from
+    /// `start` up to the end of `data` (or up to `end`, if the queue doesn't wrap around), the second one from index 0
+    /// up to `end`; the second one is empty if the queue doesn't wrap around
+    fn as_slices(&self) -> (&[u8], &[u8]) {
+        if self.start <= self.end {
+            (&self.data[self.start..self.end], &[])
         } else {
-            self.end = pos;
+            (&self.data[self.start..], &self.data[..self.end])
+        }
+    }
 
-            true
+    /// Returns an iterator over the queued elements in read order, without removing them
+    fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..self.len()).map(|i| self.data[(self.start + i) % self.data.len()])
+    }
+
+    /// Returns true if `byte` is currently on the queue; slots that were already read (or never written) are ignored
+    fn contains(&self, byte: u8) -> bool {
+        self.iter().any(|value| value == byte)
+    }
+
+    /// Returns how many elements precede the first occurrence of `byte` on the queue (i.e. its offset from the front
+    /// in read order), or None if it isn't queued
+    fn position(&self, byte: u8) -> Option<usize> {
+        self.iter().position(|value| value == byte)
+    }
+
+    /// Returns an iterator over the queued elements paired with their offset from the front of the queue, like
+    /// `iter().enumerate()`
+    fn enumerate_queued(&self) -> impl Iterator<Item = (usize, u8)> + '_ {
+        self.iter().enumerate()
+    }
+
+    /// Returns the average of the queued elements, or None if the queue is empty
+    fn mean(&self) -> Option<f64> {
+        if self.is_empty() {
+            return None;
+        }
+        let sum: u64 = self.iter().map(u64::from).sum();
+        Some(sum as f64 / self.len() as f64)
+    }
+
+    /// Replaces every queued element by the result of applying `f` to it, in read order; the free slots are left alone
+    fn map_in_place<F: FnMut(u8) -> u8>(&mut self, mut f: F) {
+        for i in 0..self.len() {
+            let index = (self.start + i) % self.data.len();
+            self.data[index] = f(self.data[index]);
         }
     }
 }
 
+/// Only the queued elements are shown (in read order); where they happen to be stored in `data` is not interesting
+impl std::fmt::Debug for RingBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RingBuffer ")?;
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Same as `RingBuffer::from_slice`. This can't fail, so there is no need for a TryFrom implementation of our own:
+/// `RingBuffer::try_from(slice)` works as well, thanks to the blanket implementation for all From conversions.
+impl From<&[u8]> for RingBuffer {
+    fn from(src: &[u8]) -> RingBuffer {
+        RingBuffer::from_slice(src)
+    }
+}
+
+/// Creates a ring buffer that just fits the elements produced by the iterator (like `RingBuffer::from_slice`), with
+/// all of them queued in the same order
+impl FromIterator<u8> for RingBuffer {
+    fn from_iter<I: IntoIterator<Item = u8>>(iter: I) -> RingBuffer {
+        RingBuffer::from_slice(&iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+/// Creates a ring buffer that just fits the contents of `deque`, with all of them queued in the same order
+impl From<VecDeque<u8>> for RingBuffer {
+    fn from(deque: VecDeque<u8>) -> RingBuffer {
+        let mut queue = RingBuffer::new(deque.len() + 1);
+        let (front, back) = deque.as_slices();
+        queue.write_slice(front);
+        queue.write_slice(back);
+        queue
+    }
+}
+
+/// Moves the queued elements into a `VecDeque`, in read order
+impl From<RingBuffer> for VecDeque<u8> {
+    fn from(queue: RingBuffer) -> VecDeque<u8> {
+        queue.iter().collect()
+    }
+}
+
 /// This function creates an "owned slice" a user-selectable size by allocating it as a vector (filled with zeros) using vec![], and then turning it
 /// into a Box<[u8]> using the into_boxed_slice() method, see https://doc.rust-lang.org/std/vec/struct.Vec.html#method.into_boxed_slice
-
 fn make_box(reqsize: usize) -> Box<[u8]> {
     vec![0; reqsize].into_boxed_slice()
 }
 
 /// This is a fun extra bit: by defining an "iterator", a ring buffer we defined ourselves can be used in for loops! (We will explain this feature in a later module!)
-
+///
+/// Since every iterator is also `IntoIterator` (as itself), `queue.into_iter()` already takes ownership of the queue
+/// and yields its elements by value in read order; the queue is dropped along with the iterator.
 impl Iterator for RingBuffer {
     type Item = u8;
 
@@ -75,7 +458,7 @@ impl Iterator for RingBuffer {
 }
 
 fn main() {
-    let mut queue = RingBuffer::new();
+    let mut queue = RingBuffer::new(16);
     assert!(queue.write(1));
     assert!(queue.write(2));
     assert!(queue.write(3));
@@ -84,4 +467,685 @@ fn main() {
     for elem in queue {
         println!("{elem}");
     }
+
+    // the rest of the API, starting with a queue that wraps around the end of its array
+    let mut queue = RingBuffer::with_usable_capacity(8);
+    queue.fill(b'.');
+    queue.read_exact_into(&mut [0; 5]).unwrap();
+    queue.write_slice(b"ring buffer");
+    println!(
+        "{queue:?}: {:?} in an array of {}, stored as {:?}, ignored {} writes",
+        queue.positions(),
+        queue.data_len(),
+        queue.as_slices(),
+        queue.dropped_writes()
+    );
+    println!(
+        "at offset 3: {:?}, first two: {:?}, then {:?}",
+        queue.peek_at(3),
+        queue.peek2(),
+        queue.read_frame(5)
+    );
+    let mut rest = [0; 8];
+    let count = queue.read_slice(&mut rest);
+    println!("and the other {count}: {:?}", &rest[..count]);
+
+    queue.write_slice_overwrite(b"abcdefghij");
+    let dropped = queue.write_overwrite(b'!');
+    println!("overwritten: {queue:?}, after dropping {dropped:?}");
+    println!(
+        "has e: {}, at {:?}, as one slice: {:?}",
+        queue.contains(b'e'),
+        queue.position(b'e'),
+        queue.as_contiguous()
+    );
+    queue.map_in_place(|byte| byte.to_ascii_uppercase());
+    let vowels = queue.drain_filter(|byte| b"AEIOU".contains(&byte));
+    println!(
+        "without {vowels:?}: {:?}",
+        queue.enumerate_queued().collect::<Vec<_>>()
+    );
+    println!(
+        "mean: {:?}, room for {:?}",
+        queue.mean(),
+        queue.write_all(&[0; 8])
+    );
+
+    queue.clear();
+    queue.compact();
+    queue.extend(1..);
+    let resized = queue.resize(16);
+    println!("refilled: {queue:?}, resized to 16: {resized}");
+    queue.reset();
+    println!("reset: {queue:?}, {:?}", RingBuffer::with_data(2, &[1, 2]));
+
+    // the thread-safe wrappers
+    let (mut producer, mut consumer) = RingBuffer::new(4).split();
+    let written = std::thread::spawn(move || {
+        (0..10)
+            .filter(|&i| producer.has_room() && producer.write(i))
+            .count()
+    });
+    let written = written.join().unwrap();
+    let mut read = Vec::new();
+    while !consumer.is_empty() {
+        read.extend(consumer.read());
+    }
+    println!("split: wrote {written}, read {read:?}");
+
+    let queue = std::sync::Arc::new(blocking::BlockingRingBuffer::new(2));
+    let producer = std::thread::spawn({
+        let queue = queue.clone();
+        move || (0..5).for_each(|i| queue.push(i))
+    });
+    let popped: Vec<_> = (0..5).map(|_| queue.pop()).collect();
+    producer.join().unwrap();
+    let timeout = std::time::Duration::from_millis(10);
+    println!(
+        "blocking: {popped:?}, then {:?} and {:?} with a timeout",
+        queue.push_timeout(5, timeout),
+        queue.pop_timeout(timeout)
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_write() {
+        let mut queue = RingBuffer::new(4);
+        assert_eq!(queue.read(), None);
+        assert!(queue.write(1));
+        assert!(queue.write(2));
+        assert_eq!(queue.peek(), Some(1));
+        assert_eq!(queue.read(), Some(1));
+        assert_eq!(queue.read(), Some(2));
+        assert_eq!(queue.read(), None);
+    }
+
+    #[test]
+    fn test_resize_preserves_order() {
+        let mut queue = RingBuffer::new(5);
+        for value in 0..4 {
+            assert!(queue.write(value));
+        }
+        assert_eq!(queue.read(), Some(0));
+        assert_eq!(queue.read(), Some(1));
+        // these writes wrap around the end of the array
+        assert!(queue.write(4));
+        assert!(queue.write(5));
+        assert!(queue.start > queue.end);
+
+        assert!(queue.resize(10));
+        assert_eq!(queue.capacity(), 9);
+        assert_eq!(queue.len(), 4);
+        assert!(queue.write(6));
+        assert_eq!(queue.collect::<Vec<_>>(), vec![2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_resize_too_small() {
+        let mut queue = RingBuffer::new(8);
+        for value in 0..5 {
+            assert!(queue.write(value));
+        }
+        assert!(!queue.resize(5));
+        assert!(!queue.resize(0));
+        assert_eq!(queue.capacity(), 7);
+        assert!(queue.resize(6));
+        assert_eq!(queue.collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_debug_shows_queue() {
+        let mut straight = RingBuffer::new(4);
+        let mut wrapped = RingBuffer::new(4);
+        for value in [9, 9, 1] {
+            assert!(wrapped.write(value));
+        }
+        assert_eq!(wrapped.read(), Some(9));
+        assert_eq!(wrapped.read(), Some(9));
+        for value in [1, 2, 3] {
+            assert!(straight.write(value));
+        }
+        assert!(wrapped.write(2));
+        assert!(wrapped.write(3));
+        assert!(wrapped.start > wrapped.end);
+
+        assert_eq!(format!("{straight:?}"), "RingBuffer [1, 2, 3]");
+        assert_eq!(format!("{wrapped:?}"), format!("{straight:?}"));
+        assert_eq!(format!("{:?}", RingBuffer::new(4)), "RingBuffer []");
+    }
+
+    #[test]
+    fn test_from_slice() {
+        let mut queue = RingBuffer::from_slice(&[1, 2, 3]);
+        assert!(queue.capacity() >= 3);
+        assert_eq!(queue.read(), Some(1));
+        assert_eq!(queue.read(), Some(2));
+        assert_eq!(queue.read(), Some(3));
+        assert_eq!(queue.read(), None);
+
+        assert!(RingBuffer::from_slice(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_with_data() {
+        let mut queue = RingBuffer::with_data(5, &[1, 2, 3]).unwrap();
+        assert_eq!(queue.data_len(), 5);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.available_write(), 1);
+        assert_eq!(queue.read(), Some(1));
+
+        // the reserved slot can't be filled
+        assert!(RingBuffer::with_data(5, &[1, 2, 3, 4]).is_ok());
+        assert!(RingBuffer::with_data(5, &[1, 2, 3, 4, 5]).is_err());
+        assert!(RingBuffer::with_data(0, &[]).is_err());
+    }
+
+    #[test]
+    fn test_contains() {
+        let mut queue = RingBuffer::new(4);
+        for value in [1, 2, 3] {
+            assert!(queue.write(value));
+        }
+        assert_eq!(queue.read(), Some(1));
+        assert!(!queue.contains(1));
+        assert!(queue.contains(2));
+        assert!(queue.contains(3));
+
+        // wrap around, so that stale data sits between `end` and `start`
+        assert!(queue.write(4));
+        assert_eq!(queue.read(), Some(2));
+        assert!(!queue.contains(2));
+        assert!(queue.contains(4));
+        assert!(!queue.contains(0));
+    }
+
+    #[test]
+    fn test_mean() {
+        let mut queue = RingBuffer::new(4);
+        assert_eq!(queue.mean(), None);
+        assert_eq!(queue.extend([255, 255, 1]), 3);
+        assert_eq!(queue.mean(), Some(511.0 / 3.0));
+
+        // wrap around, so that the stale 255s sit between `end` and `start`
+        assert_eq!(queue.read(), Some(255));
+        assert_eq!(queue.read(), Some(255));
+        assert_eq!(queue.extend([2, 3]), 2);
+        assert!(queue.start > queue.end);
+        assert_eq!(queue.mean(), Some(2.0));
+
+        queue.clear();
+        assert_eq!(queue.mean(), None);
+    }
+
+    #[test]
+    fn test_map_in_place() {
+        let mut queue = RingBuffer::new(4);
+        assert_eq!(queue.extend([1, 2, 3]), 3);
+        queue.map_in_place(|b| b + 10);
+        assert_eq!(queue.iter().collect::<Vec<_>>(), [11, 12, 13]);
+
+        // wrap around, so that a stale element sits between `end` and `start`
+        assert_eq!(queue.read(), Some(11));
+        assert!(queue.write(4));
+        assert!(queue.start > queue.end);
+        queue.map_in_place(|b| !b);
+        assert_eq!(queue.data[0], 11);
+        assert_eq!(queue.read(), Some(!12));
+        assert_eq!(queue.read(), Some(!13));
+        assert_eq!(queue.read(), Some(!4));
+        assert_eq!(queue.read(), None);
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut queue = RingBuffer::new(16);
+        assert_eq!(queue.extend(0..100), queue.capacity());
+        assert!(!queue.has_room());
+        assert_eq!(queue.extend(0..100), 0);
+        assert_eq!(queue.collect::<Vec<_>>(), (0..15).collect::<Vec<_>>());
+
+        let mut queue = RingBuffer::new(16);
+        assert_eq!(queue.extend([1, 2, 3]), 3);
+        assert_eq!(queue.len(), 3);
+    }
+
+    #[test]
+    fn test_position() {
+        let mut queue = RingBuffer::new(8);
+        assert_eq!(queue.extend(*b"xxxxx"), 5);
+        let mut discard = [0; 5];
+        assert_eq!(queue.read_slice(&mut discard), 5);
+
+        // "abc\nd" starts at index 5, so the newline ends up in the run at the start of `data`
+        assert_eq!(queue.extend(*b"abc\nd"), 5);
+        assert!(queue.start > queue.end);
+        assert_eq!(queue.data[0], b'\n');
+        assert_eq!(queue.position(b'\n'), Some(3));
+        assert_eq!(queue.position(b'a'), Some(0));
+        assert_eq!(queue.position(b'x'), None);
+
+        let mut record = [0; 8];
+        let n = queue.read_slice(&mut record[..queue.position(b'\n').unwrap()]);
+        assert_eq!(&record[..n], b"abc");
+        assert_eq!(queue.read(), Some(b'\n'));
+        assert_eq!(queue.position(b'\n'), None);
+        assert_eq!(queue.collect::<Vec<_>>(), b"d");
+    }
+
+    #[test]
+    fn test_peek_slice() {
+        let mut queue = RingBuffer::new(6);
+        assert_eq!(queue.extend([0, 0, 0, 1, 2]), 5);
+        assert_eq!(queue.read_slice(&mut [0; 3]), 3);
+        assert!(queue.write(3));
+        assert!(queue.write(4));
+        assert!(queue.start > queue.end);
+
+        let mut first = [0; 8];
+        let mut second = [0; 8];
+        assert_eq!(queue.peek_slice(&mut first), 4);
+        assert_eq!(queue.peek_slice(&mut second), 4);
+        assert_eq!(first, second);
+        assert_eq!(&first[..4], &[1, 2, 3, 4]);
+        assert_eq!(queue.len(), 4);
+
+        let mut short = [0; 3];
+        assert_eq!(queue.peek_slice(&mut short), 3);
+        assert_eq!(short, [1, 2, 3]);
+        assert_eq!(queue.read_slice(&mut short), 3);
+        assert_eq!(queue.collect::<Vec<_>>(), vec![4]);
+    }
+
+    #[test]
+    fn test_clone_is_independent() {
+        let mut original = RingBuffer::new(4);
+        assert_eq!(original.extend([1, 2, 3]), 3);
+        assert_eq!(original.read(), Some(1));
+        assert!(original.write(4));
+
+        let mut snapshot = original.clone();
+        assert_eq!(
+            (snapshot.start, snapshot.end),
+            (original.start, original.end)
+        );
+        assert_eq!(snapshot.read(), Some(2));
+        assert!(snapshot.write(5));
+        assert_eq!(original.len(), 3);
+        assert_eq!(format!("{original:?}"), "RingBuffer [2, 3, 4]");
+        assert_eq!(format!("{snapshot:?}"), "RingBuffer [3, 4, 5]");
+    }
+
+    #[test]
+    fn test_fill_until_full() {
+        for size in 1..=8 {
+            let mut queue = RingBuffer::new(size);
+            let mut written = 0;
+            while queue.has_room() {
+                assert!(queue.write(written as u8));
+                written += 1;
+            }
+            assert_eq!(written, size - 1);
+            assert_eq!(written, queue.capacity());
+            assert_eq!(queue.len(), queue.capacity());
+
+            // a failed write must leave everything alone, including the unused slot at `end`
+            let before = queue.clone();
+            assert!(!queue.write(0xff));
+            assert_eq!(queue.data, before.data);
+            assert_eq!((queue.start, queue.end), (before.start, before.end));
+
+            // and the same holds when the queued elements wrap around
+            if size > 1 {
+                assert_eq!(queue.read(), Some(0));
+                assert!(queue.has_room());
+                assert!(queue.write(0xfe));
+                assert!(!queue.has_room());
+                assert!(!queue.write(0xff));
+                assert_eq!(queue.len(), queue.capacity());
+            }
+        }
+    }
+
+    #[test]
+    fn test_clear_and_reset() {
+        let mut queue = RingBuffer::new(8);
+        assert_eq!(queue.extend(*b"secret"), 6);
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(&queue.data[..6], b"secret");
+
+        assert_eq!(queue.extend(*b"secret"), 6);
+        assert_eq!(queue.read(), Some(b's'));
+        queue.reset();
+        assert!(queue.is_empty());
+        assert_eq!((queue.start, queue.end), (0, 0));
+        assert!(queue.data.iter().all(|&byte| byte == 0));
+        assert_eq!(queue.capacity(), 7);
+    }
+
+    #[test]
+    fn test_write_slice() {
+        let mut queue = RingBuffer::new(6);
+        assert_eq!(queue.write_slice(&[0, 0, 0]), 3);
+        assert_eq!(queue.read_slice(&mut [0; 3]), 3);
+        assert_eq!(queue.available_write(), 5);
+        // this write wraps around the end of `data`, and doesn't fit completely
+        assert_eq!(queue.write_slice(&[1, 2, 3, 4, 5, 6, 7]), 5);
+        assert_eq!(queue.available_write(), 0);
+        assert_eq!(queue.write_slice(&[8]), 0);
+        assert_eq!(queue.collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_write_all() {
+        let mut queue = RingBuffer::new(6);
+        assert_eq!(queue.write_all(&[1, 2, 3]), Ok(()));
+        let before = queue.clone();
+        assert_eq!(queue.write_all(&[4, 5, 6]), Err(2));
+        assert_eq!(queue.data, before.data);
+        assert_eq!((queue.start, queue.end), (before.start, before.end));
+        assert_eq!(queue.write_all(&[4, 5]), Ok(()));
+        assert_eq!(queue.write_all(&[]), Ok(()));
+        assert_eq!(queue.write_all(&[6]), Err(0));
+        assert_eq!(queue.collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_write_all_wrapped() {
+        let mut queue = RingBuffer::new(5);
+        assert_eq!(queue.write_all(&[0, 0, 0]), Ok(()));
+        assert_eq!(queue.read_frame(3), Some(vec![0, 0, 0]));
+        // one byte too many is refused, without writing anything
+        assert_eq!(queue.write_all(&[1, 2, 3, 4, 5]), Err(4));
+        assert!(queue.is_empty());
+        // while a slice that exactly fits is written across the wraparound point
+        assert_eq!(queue.write_all(&[1, 2, 3, 4]), Ok(()));
+        assert!(!queue.has_room());
+        assert_eq!(queue.as_slices(), (&[1, 2][..], &[3, 4][..]));
+    }
+
+    #[test]
+    fn test_enumerate_queued() {
+        let mut queue = RingBuffer::new(5);
+        assert_eq!(queue.write_slice(&[0, 0, 0]), 3);
+        assert_eq!(queue.read_slice(&mut [0; 3]), 3);
+        assert_eq!(queue.write_slice(&[10, 20, 30, 40]), 4);
+        assert!(queue.start > queue.end);
+
+        let pairs: Vec<_> = queue.enumerate_queued().collect();
+        assert_eq!(pairs, vec![(0, 10), (1, 20), (2, 30), (3, 40)]);
+        // nothing was consumed
+        assert_eq!(queue.len(), 4);
+    }
+
+    #[test]
+    fn test_positions() {
+        let mut queue = RingBuffer::new(4);
+        assert_eq!(queue.data_len(), 4);
+        assert_eq!(queue.positions(), (0, 0));
+        assert_eq!(queue.extend([1, 2, 3]), 3);
+        assert_eq!(queue.positions(), (0, 3));
+
+        assert_eq!(queue.read(), Some(1));
+        assert_eq!(queue.read(), Some(2));
+        assert!(queue.write(4));
+        let (start, end) = queue.positions();
+        assert_eq!((start, end), (2, 0));
+        // the queued elements are `data[start..]` followed by `data[..end]`
+        assert_eq!(queue.as_slices(), (&[3, 4][..], &[][..]));
+        assert_eq!(queue.data_len() - start + end, queue.len());
+    }
+
+    #[test]
+    fn test_dropped_writes() {
+        let mut queue = RingBuffer::new(4);
+        assert_eq!(queue.extend([1, 2, 3, 4]), 3);
+        assert_eq!(queue.dropped_writes(), 0);
+        for value in 4..9 {
+            assert!(!queue.write(value));
+        }
+        assert_eq!(queue.dropped_writes(), 5);
+
+        // making room doesn't reset the counter, but clearing does
+        assert_eq!(queue.read(), Some(1));
+        assert!(queue.write(4));
+        assert!(!queue.write(5));
+        assert_eq!(queue.dropped_writes(), 6);
+        queue.clear();
+        assert_eq!(queue.dropped_writes(), 0);
+    }
+
+    #[test]
+    fn test_as_contiguous() {
+        let mut queue = RingBuffer::new(5);
+        assert_eq!(queue.extend([0, 1, 2, 3]), 4);
+        assert_eq!(queue.read(), Some(0));
+        assert_eq!(queue.read(), Some(1));
+        assert_eq!(queue.extend([4, 5]), 2);
+        assert!(queue.start > queue.end);
+
+        assert_eq!(queue.as_contiguous(), &[2, 3, 4, 5]);
+        assert_eq!((queue.start, queue.end), (0, 4));
+        assert_eq!(queue.read(), Some(2));
+        assert!(queue.write(6));
+        assert!(!queue.write(7));
+        assert_eq!(queue.collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+
+        assert_eq!(RingBuffer::new(3).as_contiguous(), &[]);
+    }
+
+    #[test]
+    fn test_from_byte_slice() {
+        // the TryFrom<&[u8]> implementation is the blanket one for From, so it never fails
+        #[allow(clippy::unnecessary_fallible_conversions)]
+        let Ok(mut queue) = RingBuffer::try_from(&[1, 2, 3][..]);
+        assert_eq!(queue.capacity(), 3);
+        assert_eq!(queue.read(), Some(1));
+        assert_eq!(queue.read(), Some(2));
+        assert_eq!(queue.read(), Some(3));
+        assert_eq!(queue.read(), None);
+
+        assert_eq!(
+            RingBuffer::from(&[1, 2, 3][..]).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        let queue: RingBuffer = b"abc"[..].into();
+        assert!(!queue.has_room());
+        assert_eq!(queue.collect::<Vec<_>>(), b"abc");
+    }
+
+    #[test]
+    fn test_vec_deque_round_trip() {
+        // make the deque wrap around, so its contents are in two slices
+        let mut deque = VecDeque::with_capacity(4);
+        deque.extend([9, 9, 9, 1]);
+        deque.drain(..3);
+        deque.extend([2, 3]);
+        let mut queue = RingBuffer::from(deque.clone());
+        assert_eq!(queue.len(), 3);
+        assert!(!queue.has_room());
+
+        assert_eq!(queue.read(), Some(1));
+        assert_eq!(VecDeque::from(queue), VecDeque::from([2, 3]));
+        deque.pop_front();
+        assert_eq!(VecDeque::from(RingBuffer::from(deque.clone())), deque);
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut queue = RingBuffer::new(6);
+        assert_eq!(queue.extend([1, 2]), 2);
+        assert_eq!(queue.fill(0), 3);
+        assert!(!queue.has_room());
+        assert_eq!(queue.fill(0), 0);
+        assert_eq!(queue.collect::<Vec<_>>(), vec![1, 2, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_read_frame() {
+        let mut queue = RingBuffer::new(8);
+        assert_eq!(queue.extend([1, 2, 3, 4, 5]), 5);
+        assert_eq!(queue.read_frame(8), None);
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.read_frame(3), Some(vec![1, 2, 3]));
+        assert_eq!(queue.read_frame(3), None);
+        assert_eq!(queue.read_frame(2), Some(vec![4, 5]));
+        assert_eq!(queue.read_frame(0), Some(vec![]));
+    }
+
+    #[test]
+    fn test_drain_filter() {
+        let mut queue = RingBuffer::new(6);
+        assert_eq!(queue.extend([9, 9, 0]), 3);
+        assert_eq!(queue.read_frame(2), Some(vec![9, 9]));
+        // this wraps around the end of the array
+        assert_eq!(queue.extend([1, 0, 2, 3]), 4);
+        assert_eq!(queue.drain_filter(|value| value == 0), vec![0, 0]);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.dropped_writes(), 0);
+        assert_eq!(queue.collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_with_usable_capacity() {
+        let mut queue = RingBuffer::with_usable_capacity(15);
+        assert_eq!(queue.capacity(), 15);
+        assert_eq!(queue.fill(7), 15);
+        assert!(!queue.write(7));
+
+        let mut queue = RingBuffer::with_usable_capacity(0);
+        assert_eq!(queue.capacity(), 0);
+        assert!(!queue.write(7));
+    }
+
+    #[test]
+    fn test_as_slices() {
+        let mut queue = RingBuffer::new(5);
+        assert_eq!(queue.as_slices(), (&[][..], &[][..]));
+        assert_eq!(queue.extend([0, 1, 2, 3]), 4);
+        assert_eq!(queue.as_slices(), (&[0, 1, 2, 3][..], &[][..]));
+        assert_eq!(queue.read_frame(2), Some(vec![0, 1]));
+        assert_eq!(queue.extend([4, 5]), 2);
+        assert_eq!(queue.as_slices(), (&[2, 3, 4][..], &[5][..]));
+    }
+
+    #[test]
+    fn test_read_exact_into() {
+        let mut queue = RingBuffer::new(5);
+        assert_eq!(queue.extend([1, 2, 3, 4]), 4);
+        let mut dst = [0; 3];
+        assert_eq!(queue.read_exact_into(&mut dst), Ok(()));
+        assert_eq!(dst, [1, 2, 3]);
+
+        // there is only one element left, so nothing is read
+        let mut dst = [0; 2];
+        assert_eq!(queue.read_exact_into(&mut dst), Err(1));
+        assert_eq!(dst, [0, 0]);
+        assert_eq!(queue.len(), 1);
+
+        // this read wraps around the end of the array
+        assert_eq!(queue.extend([5, 6]), 2);
+        assert!(queue.start > queue.end);
+        let mut dst = [0; 3];
+        assert_eq!(queue.read_exact_into(&mut dst), Ok(()));
+        assert_eq!(dst, [4, 5, 6]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_peek_at() {
+        let mut queue = RingBuffer::new(4);
+        assert_eq!(queue.peek2(), None);
+        assert!(queue.write(0xca));
+        assert_eq!(queue.peek_at(0), Some(0xca));
+        assert_eq!(queue.peek_at(1), None);
+        assert_eq!(queue.peek2(), None);
+
+        // make the two bytes straddle the wraparound point
+        assert_eq!(queue.extend([9, 9]), 2);
+        assert_eq!(queue.read_frame(3), Some(vec![0xca, 9, 9]));
+        assert!(queue.write(0xfe));
+        assert!(queue.write(0xed));
+        assert_eq!((queue.start, queue.end), (3, 1));
+        assert_eq!(queue.peek2(), Some((0xfe, 0xed)));
+        assert_eq!(queue.peek_at(1), Some(0xed));
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_write_overwrite() {
+        let mut queue = RingBuffer::new(4);
+        assert_eq!(queue.write_overwrite(1), None);
+        assert_eq!(queue.extend([2, 3]), 2);
+        assert_eq!(queue.write_overwrite(4), Some(1));
+        assert_eq!(queue.write_overwrite(5), Some(2));
+        assert_eq!(queue.dropped_writes(), 0);
+        assert_eq!(queue.clone().collect::<Vec<_>>(), vec![3, 4, 5]);
+
+        assert_eq!(RingBuffer::new(1).write_overwrite(1), Some(1));
+    }
+
+    #[test]
+    fn test_write_slice_overwrite() {
+        let mut queue = RingBuffer::new(6);
+        assert_eq!(queue.extend([1, 2, 3]), 3);
+        queue.write_slice_overwrite(&[4, 5, 6]);
+        assert_eq!(queue.clone().collect::<Vec<_>>(), vec![2, 3, 4, 5, 6]);
+
+        // only the tail of a slice that is too long for the queue is kept
+        queue.write_slice_overwrite(&[10, 11, 12, 13, 14, 15, 16]);
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.clone().collect::<Vec<_>>(), vec![12, 13, 14, 15, 16]);
+
+        queue.write_slice_overwrite(&[]);
+        assert_eq!(queue.len(), 5);
+        let mut empty = RingBuffer::new(1);
+        empty.write_slice_overwrite(&[1, 2]);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let mut queue = RingBuffer::new(4);
+        assert_eq!(queue.extend([0, 0, 1]), 3);
+        assert_eq!(queue.read_frame(2), Some(vec![0, 0]));
+        assert_eq!(queue.extend([2, 3]), 2);
+        assert!(queue.start > queue.end);
+        // a for loop takes ownership through IntoIterator
+        let mut values = Vec::new();
+        for value in queue {
+            values.push(value);
+        }
+        assert_eq!(values, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let queue: RingBuffer = (0u8..5).collect();
+        assert_eq!(queue.capacity(), 5);
+        assert!(!queue.has_room());
+        assert_eq!(queue.collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+
+        let queue: RingBuffer = std::iter::empty().collect();
+        assert_eq!(queue.capacity(), 0);
+    }
+
+    #[test]
+    fn test_compact() {
+        let mut queue = RingBuffer::new(5);
+        assert_eq!(queue.extend([0, 0, 0, 1]), 4);
+        assert_eq!(queue.read_frame(3), Some(vec![0, 0, 0]));
+        assert_eq!(queue.extend([2, 3]), 2);
+        assert!(!queue.as_slices().1.is_empty());
+
+        queue.compact();
+        assert_eq!((queue.start, queue.end), (0, 3));
+        assert_eq!(queue.as_slices(), (&[1, 2, 3][..], &[][..]));
+        assert_eq!(queue.extend([4, 5]), 1);
+        assert_eq!(queue.collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
 }
@@ -0,0 +1,150 @@
+//! A ring buffer can be shared between a producer thread and a consumer thread without any locking, as long as there
+//! is only one of each: the producer is the only one that moves `end`, and the consumer is the only one that moves
+//! `start`. Publishing a new `end` with Release ordering (and loading it with Acquire ordering) guarantees that the
+//! consumer sees the data written before it; the same goes for `start` and a slot that the producer may reuse.
+
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::RingBuffer;
+
+struct Shared {
+    data: Box<[UnsafeCell<u8>]>,
+    start: AtomicUsize,
+    end: AtomicUsize,
+}
+
+// SAFETY: the producer only writes to the slot at `end` before publishing it, and the consumer only reads the slots
+// from `start` up to (but not including) the published `end`; so a slot is never accessed by both at the same time
+unsafe impl Sync for Shared {}
+
+/// The writing half of a split ring buffer
+pub struct Producer {
+    shared: Arc<Shared>,
+}
+
+/// The reading half of a split ring buffer
+pub struct Consumer {
+    shared: Arc<Shared>,
+}
+
+impl RingBuffer {
+    /// This function turns the ring buffer into a producer and a consumer, which can be moved to different threads;
+    /// anything that was already queued can still be read by the consumer
+    pub fn split(self) -> (Producer, Consumer) {
+        let shared = Arc::new(Shared {
+            data: self
+                .data
+                .iter()
+                .map(|&value| UnsafeCell::new(value))
+                .collect(),
+            start: AtomicUsize::new(self.start),
+            end: AtomicUsize::new(self.end),
+        });
+        let producer = Producer {
+            shared: Arc::clone(&shared),
+        };
+        (producer, Consumer { shared })
+    }
+}
+
+impl Producer {
+    /// This function tries to put `value` on the queue; and returns true if this succeeds
+    /// It returns false if the queue was full
+    pub fn write(&mut self, value: u8) -> bool {
+        let shared = &*self.shared;
+        // only we ever store to `end`, so there is nothing to synchronize with
+        let end = shared.end.load(Ordering::Relaxed);
+        let pos = (end + 1) % shared.data.len();
+        if pos == shared.start.load(Ordering::Acquire) {
+            return false;
+        }
+        // SAFETY: the slot at `end` is not visible to the consumer until we publish `pos` below
+        unsafe { *shared.data[end].get() = value };
+        shared.end.store(pos, Ordering::Release);
+        true
+    }
+
+    /// Returns true if writing will succeed; this can only change from false to true while we aren't looking
+    pub fn has_room(&self) -> bool {
+        let shared = &*self.shared;
+        let end = shared.end.load(Ordering::Relaxed);
+        (end + 1) % shared.data.len() != shared.start.load(Ordering::Acquire)
+    }
+}
+
+impl Consumer {
+    /// This function tries to read a value from the queue and returns Some(value) if this succeeds,
+    /// it returns None if the queue was empty
+    pub fn read(&mut self) -> Option<u8> {
+        let shared = &*self.shared;
+        // only we ever store to `start`, so there is nothing to synchronize with
+        let start = shared.start.load(Ordering::Relaxed);
+        if start == shared.end.load(Ordering::Acquire) {
+            return None;
+        }
+        // SAFETY: the producer published this slot, and won't touch it again until we move `start` past it
+        let value = unsafe { *shared.data[start].get() };
+        shared
+            .start
+            .store((start + 1) % shared.data.len(), Ordering::Release);
+        Some(value)
+    }
+
+    /// Returns true if there is nothing to read; this can only change from true to false while we aren't looking
+    pub fn is_empty(&self) -> bool {
+        let shared = &*self.shared;
+        shared.start.load(Ordering::Relaxed) == shared.end.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_split_keeps_contents() {
+        let mut queue = RingBuffer::new(4);
+        assert_eq!(queue.extend([1, 2]), 2);
+        let (mut producer, mut consumer) = queue.split();
+        assert!(producer.write(3));
+        assert!(!producer.has_room());
+        assert!(!producer.write(4));
+        assert_eq!(consumer.read(), Some(1));
+        assert_eq!(consumer.read(), Some(2));
+        assert_eq!(consumer.read(), Some(3));
+        assert_eq!(consumer.read(), None);
+        assert!(consumer.is_empty());
+    }
+
+    #[test]
+    fn test_two_threads() {
+        const COUNT: usize = 1_000_000;
+        let (mut producer, mut consumer) = RingBuffer::new(64).split();
+
+        let writer = thread::spawn(move || {
+            for i in 0..COUNT {
+                while !producer.write(i as u8) {
+                    thread::yield_now();
+                }
+            }
+        });
+        let reader = thread::spawn(move || {
+            for i in 0..COUNT {
+                let value = loop {
+                    match consumer.read() {
+                        Some(value) => break value,
+                        None => thread::yield_now(),
+                    }
+                };
+                assert_eq!(value, i as u8);
+            }
+            consumer.is_empty()
+        });
+
+        writer.join().unwrap();
+        assert!(reader.join().unwrap());
+    }
+}
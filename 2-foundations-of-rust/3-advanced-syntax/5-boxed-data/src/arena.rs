@@ -0,0 +1,211 @@
+//! Every node of an `Expr` is allocated separately, which makes building and evaluating large expressions slow. An
+//! `ExprArena` instead stores all nodes of an expression next to each other in a single `Vec`, where nodes refer to
+//! their operands by their index (a `NodeId`) in that `Vec`.
+
+use crate::{checked_clamp, eval_sigma, BinaryOp, Expr, UnaryOp};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct NodeId(usize);
+
+// the same as `Expr`, but with `NodeId`s in place of boxed subexpressions
+#[derive(PartialEq, Debug)]
+pub enum ArenaNode {
+    Const(i64),
+    Add(NodeId, NodeId),
+    Sub(NodeId, NodeId),
+    Mul(NodeId, NodeId),
+    Div(NodeId, NodeId),
+    Mod(NodeId, NodeId),
+    Var,
+    Summation(Vec<NodeId>),
+    Product(Vec<NodeId>),
+    Sigma(NodeId, NodeId, NodeId),
+    SigmaStep(NodeId, NodeId, NodeId, NodeId),
+    If(NodeId, NodeId, NodeId),
+    Min(NodeId, NodeId),
+    Max(NodeId, NodeId),
+    Clamp(NodeId, NodeId, NodeId),
+    Isqrt(NodeId),
+    Sqrt(NodeId),
+    Gcd(NodeId, NodeId),
+    Lcm(NodeId, NodeId),
+    Fact(NodeId),
+    Call(String, Vec<NodeId>),
+}
+
+#[derive(Default, Debug)]
+pub struct ExprArena {
+    nodes: Vec<ArenaNode>,
+}
+
+impl ExprArena {
+    pub fn new() -> ExprArena {
+        ExprArena::default()
+    }
+
+    // adds `node` to the arena; the operands it refers to must already have been added
+    pub fn push(&mut self, node: ArenaNode) -> NodeId {
+        self.nodes.push(node);
+        NodeId(self.nodes.len() - 1)
+    }
+
+    pub fn get(&self, id: NodeId) -> &ArenaNode {
+        &self.nodes[id.0]
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    // adds all nodes of `expr`, returning the id of its root
+    pub fn push_expr(&mut self, expr: &Expr) -> NodeId {
+        let mut push = |e: &Expr| self.push_expr(e);
+        let node = match expr {
+            Expr::Const(k) => ArenaNode::Const(*k),
+            Expr::Var => ArenaNode::Var,
+            Expr::Add(lhs, rhs) => ArenaNode::Add(push(lhs), push(rhs)),
+            Expr::Sub(lhs, rhs) => ArenaNode::Sub(push(lhs), push(rhs)),
+            Expr::Mul(lhs, rhs) => ArenaNode::Mul(push(lhs), push(rhs)),
+            Expr::Div(lhs, rhs) => ArenaNode::Div(push(lhs), push(rhs)),
+            Expr::Mod(lhs, rhs) => ArenaNode::Mod(push(lhs), push(rhs)),
+            Expr::Summation(exprs) => ArenaNode::Summation(exprs.iter().map(push).collect()),
+            Expr::Product(exprs) => ArenaNode::Product(exprs.iter().map(push).collect()),
+            Expr::Sigma(from, to, body) => ArenaNode::Sigma(push(from), push(to), push(body)),
+            Expr::SigmaStep(from, to, step, body) => {
+                ArenaNode::SigmaStep(push(from), push(to), push(step), push(body))
+            }
+            Expr::If(cond, then, otherwise) => {
+                ArenaNode::If(push(cond), push(then), push(otherwise))
+            }
+            Expr::Min(lhs, rhs) => ArenaNode::Min(push(lhs), push(rhs)),
+            Expr::Max(lhs, rhs) => ArenaNode::Max(push(lhs), push(rhs)),
+            Expr::Clamp(value, lo, hi) => ArenaNode::Clamp(push(value), push(lo), push(hi)),
+            Expr::Isqrt(operand) => ArenaNode::Isqrt(push(operand)),
+            Expr::Sqrt(operand) => ArenaNode::Sqrt(push(operand)),
+            Expr::Gcd(lhs, rhs) => ArenaNode::Gcd(push(lhs), push(rhs)),
+            Expr::Lcm(lhs, rhs) => ArenaNode::Lcm(push(lhs), push(rhs)),
+            Expr::Fact(operand) => ArenaNode::Fact(push(operand)),
+            Expr::Call(name, args) => {
+                ArenaNode::Call(name.clone(), args.iter().map(push).collect())
+            }
+        };
+        self.push(node)
+    }
+}
+
+pub fn from_expr(expr: &Expr) -> (ExprArena, NodeId) {
+    let mut arena = ExprArena::new();
+    let root = arena.push_expr(expr);
+    (arena, root)
+}
+
+// evaluates the expression rooted at `root` exactly like `eval` would
+pub fn eval_arena(arena: &ExprArena, root: NodeId, var: i64) -> Option<i64> {
+    use ArenaNode::*;
+    let eval = |id: NodeId| eval_arena(arena, id, var);
+    match arena.get(root) {
+        Const(k) => Some(*k),
+        Var => Some(var),
+        Add(lhs, rhs) => BinaryOp::Add.apply(eval(*lhs)?, eval(*rhs)?),
+        Sub(lhs, rhs) => BinaryOp::Sub.apply(eval(*lhs)?, eval(*rhs)?),
+        Mul(lhs, rhs) => BinaryOp::Mul.apply(eval(*lhs)?, eval(*rhs)?),
+        Div(lhs, rhs) => BinaryOp::Div.apply(eval(*lhs)?, eval(*rhs)?),
+        Mod(lhs, rhs) => BinaryOp::Mod.apply(eval(*lhs)?, eval(*rhs)?),
+        Summation(ids) => ids
+            .iter()
+            .try_fold(0i64, |acc, id| acc.checked_add(eval(*id)?)),
+        Product(ids) => ids
+            .iter()
+            .try_fold(1i64, |acc, id| acc.checked_mul(eval(*id)?)),
+        Sigma(from, to, body) => {
+            eval_sigma(eval(*from)?, eval(*to)?, 1, |i| eval_arena(arena, *body, i))
+        }
+        SigmaStep(from, to, step, body) => {
+            let (from, to, step) = (eval(*from)?, eval(*to)?, eval(*step)?);
+            eval_sigma(from, to, step, |i| eval_arena(arena, *body, i))
+        }
+        If(cond, then, otherwise) => {
+            if eval(*cond)? != 0 {
+                eval(*then)
+            } else {
+                eval(*otherwise)
+            }
+        }
+        Min(lhs, rhs) => BinaryOp::Min.apply(eval(*lhs)?, eval(*rhs)?),
+        Max(lhs, rhs) => BinaryOp::Max.apply(eval(*lhs)?, eval(*rhs)?),
+        Clamp(value, lo, hi) => checked_clamp(eval(*value)?, eval(*lo)?, eval(*hi)?),
+        Isqrt(operand) | Sqrt(operand) => UnaryOp::Isqrt.apply(eval(*operand)?),
+        Gcd(lhs, rhs) => BinaryOp::Gcd.apply(eval(*lhs)?, eval(*rhs)?),
+        Lcm(lhs, rhs) => BinaryOp::Lcm.apply(eval(*lhs)?, eval(*rhs)?),
+        Fact(operand) => UnaryOp::Fact.apply(eval(*operand)?),
+        // like `eval`, this knows no functions
+        Call(..) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::random::random_expr;
+    use crate::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_matches_eval() {
+        let exprs = [
+            add(mul(Var, Const(3)), Const(2)),
+            Summation(vec![Var, sub(Var, Const(1)), div(Const(100), Var)]),
+            rem(Const(100), sub(Var, Const(2))),
+            sigma(Const(1), Var, mul(Var, Var)),
+            sigma_step(Var, Const(0), Const(-2), add(Var, Const(1))),
+            if_then_else(Var, div(Const(10), Var), max(Var, Const(-3))),
+            gcd(isqrt(Var), lcm(Var, Const(4))),
+            sqrt(sub(Var, Const(1))),
+            mul(Var, Var),
+            Product(vec![Var, Const(-2), add(Var, Const(1))]),
+            fact(add(Var, Const(3))),
+            clamp(Var, Const(-3), Const(8)),
+            clamp(Const(0), Var, Const(5)),
+        ];
+        for expr in &exprs {
+            let (arena, root) = from_expr(expr);
+            for var in [-5, 0, 1, 7, 100, i64::MAX] {
+                assert_eq!(eval_arena(&arena, root, var), eval(expr, var), "{expr:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_eval_on_random_exprs() {
+        let mut rng = StdRng::seed_from_u64(4);
+        for _ in 0..2000 {
+            let expr = random_expr(&mut rng, 5, true);
+            let (arena, root) = from_expr(&expr);
+            for var in [-3, 0, 1, 5, 1000, i64::MIN] {
+                assert_eq!(eval_arena(&arena, root, var), eval(&expr, var), "{expr:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_directly() {
+        // the sum 1 + 2 + ... + 1000, built as a chain of additions without ever making an `Expr`
+        let mut arena = ExprArena::new();
+        assert!(arena.is_empty());
+        let mut acc = arena.push(ArenaNode::Const(0));
+        for k in 1..=1000 {
+            let k = arena.push(ArenaNode::Const(k));
+            acc = arena.push(ArenaNode::Add(acc, k));
+        }
+        assert_eq!(arena.len(), 2001);
+        assert_eq!(eval_arena(&arena, acc, 0), Some(500500));
+
+        let (arena, root) = from_expr(&add(Var, Const(1)));
+        assert_eq!(arena.get(root), &ArenaNode::Add(NodeId(0), NodeId(1)));
+    }
+}
@@ -0,0 +1,116 @@
+//! Nesting the convenience functions gets hard to read for larger expressions; an `ExprBuilder` instead applies
+//! operators one after the other, with the expression built so far as their left operand. For instance
+//! `ExprBuilder::var().mul(3).add(5).build()` gives `add(mul(Var, Const(3)), Const(5))`.
+
+use crate::{Const, Expr, Var};
+
+pub struct ExprBuilder {
+    expr: Expr,
+}
+
+// these methods are named after the operators on purpose, instead of overloading the operators themselves
+#[allow(clippy::should_implement_trait)]
+impl ExprBuilder {
+    pub fn new(expr: impl Into<Expr>) -> ExprBuilder {
+        ExprBuilder { expr: expr.into() }
+    }
+
+    pub fn var() -> ExprBuilder {
+        ExprBuilder::new(Var)
+    }
+
+    pub fn constant(k: i64) -> ExprBuilder {
+        ExprBuilder::new(Const(k))
+    }
+
+    pub fn add(self, rhs: impl Into<Expr>) -> ExprBuilder {
+        ExprBuilder::new(crate::add(self.expr, rhs.into()))
+    }
+
+    pub fn sub(self, rhs: impl Into<Expr>) -> ExprBuilder {
+        ExprBuilder::new(crate::sub(self.expr, rhs.into()))
+    }
+
+    pub fn mul(self, rhs: impl Into<Expr>) -> ExprBuilder {
+        ExprBuilder::new(crate::mul(self.expr, rhs.into()))
+    }
+
+    pub fn div(self, rhs: impl Into<Expr>) -> ExprBuilder {
+        ExprBuilder::new(crate::div(self.expr, rhs.into()))
+    }
+
+    pub fn rem(self, rhs: impl Into<Expr>) -> ExprBuilder {
+        ExprBuilder::new(crate::rem(self.expr, rhs.into()))
+    }
+
+    pub fn min(self, rhs: impl Into<Expr>) -> ExprBuilder {
+        ExprBuilder::new(crate::min(self.expr, rhs.into()))
+    }
+
+    pub fn max(self, rhs: impl Into<Expr>) -> ExprBuilder {
+        ExprBuilder::new(crate::max(self.expr, rhs.into()))
+    }
+
+    pub fn gcd(self, rhs: impl Into<Expr>) -> ExprBuilder {
+        ExprBuilder::new(crate::gcd(self.expr, rhs.into()))
+    }
+
+    pub fn lcm(self, rhs: impl Into<Expr>) -> ExprBuilder {
+        ExprBuilder::new(crate::lcm(self.expr, rhs.into()))
+    }
+
+    pub fn isqrt(self) -> ExprBuilder {
+        ExprBuilder::new(crate::isqrt(self.expr))
+    }
+
+    pub fn build(self) -> Expr {
+        self.expr
+    }
+}
+
+impl From<ExprBuilder> for Expr {
+    fn from(builder: ExprBuilder) -> Expr {
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn test_builder() {
+        assert_eq!(
+            ExprBuilder::var().mul(3).add(Expr::from(5)).build(),
+            add(mul(Var, Const(3)), Const(5))
+        );
+        // operators are applied left-associatively
+        assert_eq!(
+            ExprBuilder::constant(10).sub(Var).sub(1).build(),
+            sub(sub(Const(10), Var), Const(1))
+        );
+        // builders can be used as operands too
+        assert_eq!(
+            ExprBuilder::var()
+                .div(ExprBuilder::var().add(1))
+                .max(0)
+                .isqrt()
+                .build(),
+            isqrt(max(div(Var, add(Var, Const(1))), Const(0)))
+        );
+        assert_eq!(
+            ExprBuilder::var()
+                .rem(7)
+                .min(3)
+                .gcd(ExprBuilder::constant(6))
+                .lcm(4)
+                .build(),
+            lcm(gcd(min(rem(Var, Const(7)), Const(3)), Const(6)), Const(4))
+        );
+        assert_eq!(
+            ExprBuilder::new(Summation(vec![])).build(),
+            Summation(vec![])
+        );
+    }
+}
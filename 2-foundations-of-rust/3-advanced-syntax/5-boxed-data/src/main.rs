@@ -6,22 +6,58 @@
 /// - Add support for multiplication and division
 ///
 /// - We have added the form "Summation(Vec<Expr>)", representing the sum of a list of expressions.
-/// Question: why can we get away with Vec<Expr> enough in that case, instead of Box<Vec<Expr>> ?
+///   Question: why can we get away with Vec<Expr> enough in that case, instead of Box<Vec<Expr>> ?
 ///
 /// - EXTRA: Since division can fail, the function eval needs to return an Option<i64>, where None indicates that a division by
 ///   zero has occurred. Can you change the code so that that errors are propagated correctly? (hint: use the ? syntax).
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
 enum Expr {
     Const(i64),
     Add(Box<Expr>, Box<Expr>),
     Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    // the remainder of dividing the first operand by the second; it has the same sign as the first operand
+    Mod(Box<Expr>, Box<Expr>),
     Var,
     Summation(Vec<Expr>),
+    // the product of a list of expressions, like Summation is their sum
+    Product(Vec<Expr>),
+    // Sigma(from, to, body): the sum of `body` for Var = from, from + 1, ..., to
+    Sigma(Box<Expr>, Box<Expr>, Box<Expr>),
+    // SigmaStep(from, to, step, body): like Sigma, but Var advances by `step` (which may be negative)
+    SigmaStep(Box<Expr>, Box<Expr>, Box<Expr>, Box<Expr>),
+    // If(cond, then, else): `then` if `cond` is non-zero, `else` otherwise; only the chosen branch is evaluated
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+    Min(Box<Expr>, Box<Expr>),
+    Max(Box<Expr>, Box<Expr>),
+    // Clamp(value, lo, hi): `value` bounded to lo..=hi, i.e. max(lo, min(value, hi)); undefined if lo > hi
+    Clamp(Box<Expr>, Box<Expr>, Box<Expr>),
+    // the integer square root, rounded down
+    Isqrt(Box<Expr>),
+    // the square root; this is only different from Isqrt in `eval_f64`, since `eval` can only round it down as well
+    Sqrt(Box<Expr>),
+    // the greatest common divisor (always non-negative), and the least common multiple (likewise)
+    Gcd(Box<Expr>, Box<Expr>),
+    Lcm(Box<Expr>, Box<Expr>),
+    // the factorial, which is undefined for negative numbers
+    Fact(Box<Expr>),
+    // Call(name, arguments): the function called `name` (which is looked up in `eval_with_fns`), applied to the
+    // values of the arguments
+    Call(String, Vec<Expr>),
 }
 
+mod arena;
+mod builder;
+#[cfg(test)]
+mod random;
+mod rpn;
+mod sexpr;
+
 // inject these two identifiers directly into the current namespace
 use Expr::Const;
+use Expr::Product;
 use Expr::Summation;
 use Expr::Var;
 
@@ -36,39 +72,1123 @@ fn sub(x: Expr, y: Expr) -> Expr {
 }
 
 fn mul(x: Expr, y: Expr) -> Expr {
-    todo!()
+    Expr::Mul(Box::new(x), Box::new(y))
 }
 
 fn div(x: Expr, y: Expr) -> Expr {
-    todo!()
+    Expr::Div(Box::new(x), Box::new(y))
+}
+
+fn rem(x: Expr, y: Expr) -> Expr {
+    Expr::Mod(Box::new(x), Box::new(y))
+}
+
+fn sigma(from: Expr, to: Expr, body: Expr) -> Expr {
+    Expr::Sigma(Box::new(from), Box::new(to), Box::new(body))
+}
+
+fn sigma_step(from: Expr, to: Expr, step: Expr, body: Expr) -> Expr {
+    Expr::SigmaStep(Box::new(from), Box::new(to), Box::new(step), Box::new(body))
+}
+
+fn if_then_else(cond: Expr, then: Expr, otherwise: Expr) -> Expr {
+    Expr::If(Box::new(cond), Box::new(then), Box::new(otherwise))
+}
+
+fn min(x: Expr, y: Expr) -> Expr {
+    Expr::Min(Box::new(x), Box::new(y))
+}
+
+fn max(x: Expr, y: Expr) -> Expr {
+    Expr::Max(Box::new(x), Box::new(y))
+}
+
+fn clamp(value: Expr, lo: Expr, hi: Expr) -> Expr {
+    Expr::Clamp(Box::new(value), Box::new(lo), Box::new(hi))
+}
+
+fn isqrt(x: Expr) -> Expr {
+    Expr::Isqrt(Box::new(x))
+}
+
+fn sqrt(x: Expr) -> Expr {
+    Expr::Sqrt(Box::new(x))
+}
+
+fn gcd(x: Expr, y: Expr) -> Expr {
+    Expr::Gcd(Box::new(x), Box::new(y))
+}
+
+fn lcm(x: Expr, y: Expr) -> Expr {
+    Expr::Lcm(Box::new(x), Box::new(y))
+}
+
+fn fact(x: Expr) -> Expr {
+    Expr::Fact(Box::new(x))
 }
 
 // ...
 
-fn eval(expr: &Expr, var: i64) -> i64 {
-    // this should return an Option<i64>
+impl From<i64> for Expr {
+    fn from(k: i64) -> Expr {
+        Const(k)
+    }
+}
+
+impl From<i32> for Expr {
+    fn from(k: i32) -> Expr {
+        Const(k.into())
+    }
+}
+
+impl Expr {
+    // rebuilds this node, with `f` applied to each of its direct subexpressions
+    fn map_children(&self, mut f: impl FnMut(&Expr) -> Expr) -> Expr {
+        use Expr::*;
+        let mut g = |e: &Expr| Box::new(f(e));
+        match self {
+            Const(k) => Const(*k),
+            Var => Var,
+            Add(lhs, rhs) => Add(g(lhs), g(rhs)),
+            Sub(lhs, rhs) => Sub(g(lhs), g(rhs)),
+            Mul(lhs, rhs) => Mul(g(lhs), g(rhs)),
+            Div(lhs, rhs) => Div(g(lhs), g(rhs)),
+            Mod(lhs, rhs) => Mod(g(lhs), g(rhs)),
+            Summation(exprs) => Summation(exprs.iter().map(|e| *g(e)).collect()),
+            Product(exprs) => Product(exprs.iter().map(|e| *g(e)).collect()),
+            Sigma(from, to, body) => Sigma(g(from), g(to), g(body)),
+            SigmaStep(from, to, step, body) => SigmaStep(g(from), g(to), g(step), g(body)),
+            If(cond, then, otherwise) => If(g(cond), g(then), g(otherwise)),
+            Min(lhs, rhs) => Min(g(lhs), g(rhs)),
+            Max(lhs, rhs) => Max(g(lhs), g(rhs)),
+            Clamp(value, lo, hi) => Clamp(g(value), g(lo), g(hi)),
+            Isqrt(operand) => Isqrt(g(operand)),
+            Sqrt(operand) => Sqrt(g(operand)),
+            Fact(operand) => Fact(g(operand)),
+            Call(name, args) => Call(name.clone(), args.iter().map(|e| *g(e)).collect()),
+            Gcd(lhs, rhs) => Gcd(g(lhs), g(rhs)),
+            Lcm(lhs, rhs) => Lcm(g(lhs), g(rhs)),
+        }
+    }
+
+    // the name of the operator at this node in the textual formats, plus its operands; None for Const and Var. For a
+    // Call this is the name of the function, which the textual formats mark as such.
+    fn operator(&self) -> Option<(&str, Vec<&Expr>)> {
+        use Expr::*;
+        let (name, operands) = match self {
+            Const(_) | Var => return None,
+            Add(lhs, rhs) => ("+", vec![&**lhs, rhs]),
+            Sub(lhs, rhs) => ("-", vec![&**lhs, rhs]),
+            Mul(lhs, rhs) => ("*", vec![&**lhs, rhs]),
+            Div(lhs, rhs) => ("/", vec![&**lhs, rhs]),
+            Mod(lhs, rhs) => ("%", vec![&**lhs, rhs]),
+            Summation(exprs) => ("sum", exprs.iter().collect()),
+            Product(exprs) => ("product", exprs.iter().collect()),
+            Sigma(from, to, body) => ("sigma", vec![&**from, to, body]),
+            SigmaStep(from, to, step, body) => ("sigma-step", vec![&**from, to, step, body]),
+            If(cond, then, otherwise) => ("if", vec![&**cond, then, otherwise]),
+            Min(lhs, rhs) => ("min", vec![&**lhs, rhs]),
+            Max(lhs, rhs) => ("max", vec![&**lhs, rhs]),
+            Clamp(value, lo, hi) => ("clamp", vec![&**value, lo, hi]),
+            Isqrt(operand) => ("isqrt", vec![&**operand]),
+            Sqrt(operand) => ("sqrt", vec![&**operand]),
+            Gcd(lhs, rhs) => ("gcd", vec![&**lhs, rhs]),
+            Lcm(lhs, rhs) => ("lcm", vec![&**lhs, rhs]),
+            Fact(operand) => ("fact", vec![&**operand]),
+            Call(name, args) => (name.as_str(), args.iter().collect()),
+        };
+        Some((name, operands))
+    }
+
+    // whether the operator called `name` takes any number of operands
+    fn is_variadic(name: &str) -> bool {
+        matches!(name, "sum" | "product")
+    }
+
+    // the number of operands taken by the operator called `name`; None if there is no such operator or if it is
+    // variadic
+    fn arity(name: &str) -> Option<usize> {
+        match name {
+            "isqrt" | "sqrt" | "fact" => Some(1),
+            "+" | "-" | "*" | "/" | "%" | "min" | "max" | "gcd" | "lcm" => Some(2),
+            "sigma" | "if" | "clamp" => Some(3),
+            "sigma-step" => Some(4),
+            _ => None,
+        }
+    }
+
+    // the inverse of `operator`: applies the operator called `name` to `operands`, or returns None if there is no such
+    // operator or it takes a different number of operands
+    fn from_operator(name: &str, operands: Vec<Expr>) -> Option<Expr> {
+        match name {
+            "sum" => return Some(Summation(operands)),
+            "product" => return Some(Product(operands)),
+            _ => {}
+        }
+        if Expr::arity(name) != Some(operands.len()) {
+            return None;
+        }
+        let mut operands = operands.into_iter();
+        let mut next = || operands.next().unwrap();
+        let expr = match name {
+            "+" => add(next(), next()),
+            "-" => sub(next(), next()),
+            "*" => mul(next(), next()),
+            "/" => div(next(), next()),
+            "%" => rem(next(), next()),
+            "sigma" => sigma(next(), next(), next()),
+            "sigma-step" => sigma_step(next(), next(), next(), next()),
+            "if" => if_then_else(next(), next(), next()),
+            "min" => min(next(), next()),
+            "max" => max(next(), next()),
+            "clamp" => clamp(next(), next(), next()),
+            "isqrt" => isqrt(next()),
+            "sqrt" => sqrt(next()),
+            "gcd" => gcd(next(), next()),
+            "lcm" => lcm(next(), next()),
+            "fact" => fact(next()),
+            _ => unreachable!("every operator with an arity is handled"),
+        };
+        Some(expr)
+    }
+}
+
+// Writes an expression in the usual infix notation, e.g. `((x * 3) + 2)`; every binary arithmetic operation gets its
+// own parentheses, so there is no need for precedence rules. The other operators are written like function calls,
+// using the names from `Expr::operator`, e.g. `sigma(1, x, (x * x))`. A Call is marked as such, like in the textual
+// formats, e.g. `call:max(x, 3)`, so that it can't be confused with an operator of the same name.
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.operator() {
+            None => match self {
+                Const(k) => write!(f, "{k}"),
+                _ => write!(f, "x"),
+            },
+            Some((name @ ("+" | "-" | "*" | "/" | "%"), operands))
+                if !matches!(self, Expr::Call(..)) =>
+            {
+                write!(f, "({} {name} {})", operands[0], operands[1])
+            }
+            Some((name, operands)) => {
+                if let Expr::Call(..) = self {
+                    write!(f, "call:")?;
+                }
+                write!(f, "{name}(")?;
+                for (i, operand) in operands.into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{operand}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+// Writes an expression with one node per line, each operand indented two spaces more than its operator, e.g.
+//
+// *
+//   x
+//   +
+//     x
+//     1
+//
+// for `mul(Var, add(Var, Const(1)))`; operators are written using the names from `Expr::operator`, and a Call is
+// marked like in `Display`.
+fn to_tree_string(expr: &Expr) -> String {
+    let mut out = String::new();
+    write_tree(expr, 0, &mut out);
+    out
+}
+
+fn write_tree(expr: &Expr, depth: usize, out: &mut String) {
+    out.push_str(&"  ".repeat(depth));
+    match expr.operator() {
+        None => out.push_str(&expr.to_string()),
+        Some((name, operands)) => {
+            if let Expr::Call(..) = expr {
+                out.push_str("call:");
+            }
+            out.push_str(name);
+            for operand in operands {
+                out.push('\n');
+                write_tree(operand, depth + 1, out);
+            }
+        }
+    }
+}
+
+// Evaluates `expr` for the given value of Var; this fails (returning None) if the value is undefined or doesn't fit in
+// an i64. No functions are known, so evaluating a Call fails as well.
+fn eval(expr: &Expr, var: i64) -> Option<i64> {
+    eval_with_fns(expr, var, &NO_FUNCTIONS)
+}
+
+// The functions that can be called by a Call, by their name. A function is given the values of all the arguments,
+// and should fail if it doesn't take that many (or the result is undefined).
+type Functions = std::collections::HashMap<String, fn(&[i64]) -> Option<i64>>;
+
+// the functions known to `eval`, i.e. none; this is only built once, not for every evaluation
+static NO_FUNCTIONS: std::sync::LazyLock<Functions> = std::sync::LazyLock::new(Functions::new);
+
+// Evaluates `expr` like `eval`, but a Call applies the function of that name from `fns` to the values of its
+// arguments; calling a function that isn't in `fns` fails.
+fn eval_with_fns(expr: &Expr, var: i64, fns: &Functions) -> Option<i64> {
+    // `eval_with` is generic over `on_node`, so this no-op compiles away
+    eval_with(expr, var, fns, &mut |_, _| {})
+}
+
+// Evaluates `expr` like `eval`, but also returns a trace of every evaluated subexpression (in the order in which their
+// evaluation finished, so operands come before their operator) along with its value. Subexpressions are only traced
+// if they are evaluated at all: not e.g. the branch of an If that isn't taken, or the right operand of an operator
+// whose left operand failed. The body of a Sigma is traced once for every value of the summation index.
+fn eval_trace(expr: &Expr, var: i64) -> (Option<i64>, Vec<(String, Option<i64>)>) {
+    let mut trace = Vec::new();
+    let value = eval_with(expr, var, &NO_FUNCTIONS, &mut |expr, value| {
+        trace.push((expr.to_string(), value))
+    });
+    (value, trace)
+}
+
+// The evaluator behind `eval_with_fns` and `eval_trace`: `on_node` is called with every subexpression that is
+// evaluated and its value, right after the evaluation finished (so after all of its operands).
+fn eval_with(
+    expr: &Expr,
+    var: i64,
+    fns: &Functions,
+    on_node: &mut impl FnMut(&Expr, Option<i64>),
+) -> Option<i64> {
+    let value = eval_node(expr, var, fns, on_node);
+    on_node(expr, value);
+    value
+}
+
+// Evaluates the root of `expr`, and its operands through `eval_with`; this is kept apart from `eval_with` so that
+// failing (e.g. with `?`) can't skip the call to `on_node`.
+fn eval_node(
+    expr: &Expr,
+    var: i64,
+    fns: &Functions,
+    on_node: &mut impl FnMut(&Expr, Option<i64>),
+) -> Option<i64> {
     use Expr::*;
+    let mut eval = |expr: &Expr, var| eval_with(expr, var, fns, on_node);
+    match expr {
+        Const(k) => Some(*k),
+        Var => Some(var),
+        Add(lhs, rhs) => BinaryOp::Add.apply(eval(lhs, var)?, eval(rhs, var)?),
+        Sub(lhs, rhs) => BinaryOp::Sub.apply(eval(lhs, var)?, eval(rhs, var)?),
+        Mul(lhs, rhs) => BinaryOp::Mul.apply(eval(lhs, var)?, eval(rhs, var)?),
+        Div(lhs, rhs) => BinaryOp::Div.apply(eval(lhs, var)?, eval(rhs, var)?),
+        Mod(lhs, rhs) => BinaryOp::Mod.apply(eval(lhs, var)?, eval(rhs, var)?),
+        Summation(exprs) => exprs
+            .iter()
+            .try_fold(0i64, |acc, e| acc.checked_add(eval(e, var)?)),
+        Product(exprs) => exprs
+            .iter()
+            .try_fold(1i64, |acc, e| acc.checked_mul(eval(e, var)?)),
+        Sigma(from, to, body) => eval_sigma(eval(from, var)?, eval(to, var)?, 1, |i| eval(body, i)),
+        SigmaStep(from, to, step, body) => {
+            let (from, to, step) = (eval(from, var)?, eval(to, var)?, eval(step, var)?);
+            eval_sigma(from, to, step, |i| eval(body, i))
+        }
+        If(cond, then, otherwise) => {
+            if eval(cond, var)? != 0 {
+                eval(then, var)
+            } else {
+                eval(otherwise, var)
+            }
+        }
+        Min(lhs, rhs) => BinaryOp::Min.apply(eval(lhs, var)?, eval(rhs, var)?),
+        Max(lhs, rhs) => BinaryOp::Max.apply(eval(lhs, var)?, eval(rhs, var)?),
+        Clamp(value, lo, hi) => checked_clamp(eval(value, var)?, eval(lo, var)?, eval(hi, var)?),
+        Isqrt(operand) | Sqrt(operand) => UnaryOp::Isqrt.apply(eval(operand, var)?),
+        Gcd(lhs, rhs) => BinaryOp::Gcd.apply(eval(lhs, var)?, eval(rhs, var)?),
+        Lcm(lhs, rhs) => BinaryOp::Lcm.apply(eval(lhs, var)?, eval(rhs, var)?),
+        Fact(operand) => UnaryOp::Fact.apply(eval(operand, var)?),
+        Call(name, args) => {
+            let f = fns.get(name)?;
+            let args = args
+                .iter()
+                .map(|e| eval(e, var))
+                .collect::<Option<Vec<_>>>()?;
+            f(&args)
+        }
+    }
+}
+
+// Evaluates `expr` for every value of Var from `from` up to and including `to`, e.g. to plot it; the result is empty
+// if `to < from`
+fn eval_range(expr: &Expr, from: i64, to: i64) -> Vec<Option<i64>> {
+    // the constant parts don't need to be evaluated again for every value
+    let expr = precompile(expr);
+    (from..=to).map(|var| eval(&expr, var)).collect()
+}
+
+// sums `body(i)` for i = from, from + step, ... as long as i has not moved past `to`; a step of 0 would never get
+// there, so that is an error
+fn eval_sigma(
+    from: i64,
+    to: i64,
+    step: i64,
+    mut body: impl FnMut(i64) -> Option<i64>,
+) -> Option<i64> {
+    if step == 0 {
+        return None;
+    }
+    let mut acc: i64 = 0;
+    let mut i = from;
+    while (step > 0 && i <= to) || (step < 0 && i >= to) {
+        acc = acc.checked_add(body(i)?)?;
+        match i.checked_add(step) {
+            Some(next) => i = next,
+            None => break,
+        }
+    }
+    Some(acc)
+}
+
+// Evaluates `expr` like `eval`, but never fails: results that are too large or too small saturate at i64::MAX or
+// i64::MIN instead. The operations that are undefined for some operands get these values instead:
+// - dividing by zero saturates toward the sign of the numerator (so 0 / 0 = 0), and a remainder of dividing by zero
+//   is 0;
+// - the (integer) square root of a negative number is 0, and so is the factorial of a negative number;
+// - a SigmaStep with a step of 0 is 0, as if the range were empty, and so is a Clamp with lo > hi;
+// - a Sigma or SigmaStep whose body doesn't depend on the summation index is the body times the number of terms
+//   (saturated), without adding them up one by one. Otherwise its terms are added up one by one, but the evaluation
+//   as a whole adds up at most SATURATING_SIGMA_BUDGET terms (unlike in `eval`, overflowing can't end it early):
+//   once these ran out, the remaining terms of every Sigma are left out, so the result is a partial sum;
+// - a Call is 0, since no functions are known.
+fn eval_saturating(expr: &Expr, var: i64) -> i64 {
+    let mut budget = SATURATING_SIGMA_BUDGET;
+    eval_saturating_with(expr, var, &mut budget)
+}
+
+// `eval_saturating`, where `budget` is the number of Sigma terms that may still be added up one by one
+fn eval_saturating_with(expr: &Expr, var: i64, budget: &mut usize) -> i64 {
+    use Expr::*;
+    let mut eval = |expr: &Expr| eval_saturating_with(expr, var, budget);
     match expr {
         Const(k) => *k,
         Var => var,
-        Add(lhs, rhs) => eval(lhs, var) + eval(rhs, var),
-        Sub(lhs, rhs) => eval(lhs, var) - eval(rhs, var),
+        Add(lhs, rhs) => eval(lhs).saturating_add(eval(rhs)),
+        Sub(lhs, rhs) => eval(lhs).saturating_sub(eval(rhs)),
+        Mul(lhs, rhs) => eval(lhs).saturating_mul(eval(rhs)),
+        Div(lhs, rhs) => match (eval(lhs), eval(rhs)) {
+            (0, 0) => 0,
+            (a, 0) if a > 0 => i64::MAX,
+            (_, 0) => i64::MIN,
+            (a, b) => a.saturating_div(b),
+        },
+        Mod(lhs, rhs) => checked_rem(eval(lhs), eval(rhs)).unwrap_or(0),
+        Summation(exprs) => exprs.iter().fold(0, |acc, e| acc.saturating_add(eval(e))),
+        Product(exprs) => exprs.iter().fold(1, |acc, e| acc.saturating_mul(eval(e))),
+        Sigma(from, to, body) => {
+            let (from, to) = (eval(from), eval(to));
+            eval_sigma_saturating(from, to, 1, body, budget)
+        }
+        SigmaStep(from, to, step, body) => {
+            let (from, to, step) = (eval(from), eval(to), eval(step));
+            eval_sigma_saturating(from, to, step, body, budget)
+        }
+        If(cond, then, otherwise) => {
+            if eval(cond) != 0 {
+                eval(then)
+            } else {
+                eval(otherwise)
+            }
+        }
+        Min(lhs, rhs) => eval(lhs).min(eval(rhs)),
+        Max(lhs, rhs) => eval(lhs).max(eval(rhs)),
+        Clamp(value, lo, hi) => checked_clamp(eval(value), eval(lo), eval(hi)).unwrap_or(0),
+        Isqrt(operand) | Sqrt(operand) => eval(operand).max(0).isqrt(),
+        // these are never negative, so they can only overflow toward i64::MAX
+        Gcd(lhs, rhs) => checked_gcd(eval(lhs), eval(rhs)).unwrap_or(i64::MAX),
+        Lcm(lhs, rhs) => checked_lcm(eval(lhs), eval(rhs)).unwrap_or(i64::MAX),
+        Fact(operand) => match eval(operand) {
+            n if n < 0 => 0,
+            n => checked_fact(n).unwrap_or(i64::MAX),
+        },
+        Call(..) => 0,
+    }
+}
 
-        Summation(exprs) => {
-            let mut acc = 0;
-            for e in exprs {
-                acc += eval(e, var);
+// the most Sigma terms that one `eval_saturating` adds up one by one; the rest of them are left out
+const SATURATING_SIGMA_BUDGET: usize = 1 << 20;
+
+// the saturating version of `eval_sigma`, which evaluates `body` for each value of the summation index
+fn eval_sigma_saturating(from: i64, to: i64, step: i64, body: &Expr, budget: &mut usize) -> i64 {
+    // there are at most 2^64 terms, so this can't overflow
+    let terms = match step {
+        0 => 0,
+        _ if (step > 0 && from > to) || (step < 0 && from < to) => 0,
+        _ => (to as i128 - from as i128) / step as i128 + 1,
+    };
+    if terms == 0 {
+        return 0;
+    }
+    // the Var in the body is the summation index, so if it is constant, every term is the same
+    if is_constant(body) {
+        let term = eval_saturating_with(body, from, budget) as i128;
+        return term
+            .saturating_mul(terms)
+            .clamp(i64::MIN as i128, i64::MAX as i128) as i64;
+    }
+    let mut acc: i64 = 0;
+    let mut i = from;
+    while ((step > 0 && i <= to) || (step < 0 && i >= to)) && *budget > 0 {
+        *budget -= 1;
+        acc = acc.saturating_add(eval_saturating_with(body, i, budget));
+        match i.checked_add(step) {
+            Some(next) => i = next,
+            None => break,
+        }
+    }
+    acc
+}
+
+// Evaluates `expr` in floating point; this never fails, since every operation has a result according to the IEEE
+// rules: e.g. dividing by zero results in an infinity (or NaN for 0 / 0), and so does overflow. Operations that only
+// make sense for integers (Isqrt, Gcd, Lcm, Fact) result in NaN for operands that are not whole numbers; Isqrt and Sqrt
+// of a negative number are NaN as well, and so is a Clamp with lo > hi. No functions are known, so a Call is NaN too.
+fn eval_f64(expr: &Expr, var: f64) -> f64 {
+    use Expr::*;
+    let eval = |expr: &Expr| eval_f64(expr, var);
+    match expr {
+        Const(k) => *k as f64,
+        Var => var,
+        Add(lhs, rhs) => eval(lhs) + eval(rhs),
+        Sub(lhs, rhs) => eval(lhs) - eval(rhs),
+        Mul(lhs, rhs) => eval(lhs) * eval(rhs),
+        Div(lhs, rhs) => eval(lhs) / eval(rhs),
+        // like Mod, the remainder has the same sign as the first operand
+        Mod(lhs, rhs) => eval(lhs) % eval(rhs),
+        Summation(exprs) => exprs.iter().map(eval).sum(),
+        Product(exprs) => exprs.iter().map(eval).product(),
+        Sigma(from, to, body) => eval_sigma_f64(eval(from), eval(to), 1.0, |i| eval_f64(body, i)),
+        SigmaStep(from, to, step, body) => {
+            eval_sigma_f64(eval(from), eval(to), eval(step), |i| eval_f64(body, i))
+        }
+        If(cond, then, otherwise) => {
+            if eval(cond) != 0.0 {
+                eval(then)
+            } else {
+                eval(otherwise)
+            }
+        }
+        Min(lhs, rhs) => eval(lhs).min(eval(rhs)),
+        Max(lhs, rhs) => eval(lhs).max(eval(rhs)),
+        Clamp(value, lo, hi) => {
+            let (value, lo, hi) = (eval(value), eval(lo), eval(hi));
+            // this is also false if either bound is NaN
+            if lo <= hi {
+                value.clamp(lo, hi)
+            } else {
+                f64::NAN
+            }
+        }
+        Isqrt(operand) => whole(eval(operand)).sqrt().floor(),
+        Sqrt(operand) => eval(operand).sqrt(),
+        Gcd(lhs, rhs) => gcd_f64(whole(eval(lhs)), whole(eval(rhs))),
+        Lcm(lhs, rhs) => {
+            let (a, b) = (whole(eval(lhs)), whole(eval(rhs)));
+            if a == 0.0 || b == 0.0 {
+                0.0
+            } else {
+                (a / gcd_f64(a, b) * b).abs()
+            }
+        }
+        Fact(operand) => {
+            let n = whole(eval(operand));
+            if n < 0.0 {
+                return f64::NAN;
+            }
+            // the result is infinite from 171 on, so there is no need to go on beyond that
+            let mut acc: f64 = 1.0;
+            let mut i = 2.0;
+            while i <= n && acc.is_finite() {
+                acc *= i;
+                i += 1.0;
             }
             acc
         }
+        Call(..) => f64::NAN,
+    }
+}
+
+// `value` if it is a whole number, NaN otherwise (so infinities count as NaN)
+fn whole(value: f64) -> f64 {
+    if value.is_finite() && value.fract() == 0.0 {
+        value
+    } else {
+        f64::NAN
+    }
+}
+
+// Euclid's algorithm, like `checked_gcd`; the operands must be whole numbers (or NaN)
+fn gcd_f64(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        return f64::NAN;
+    }
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0.0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// The floating point version of `eval_sigma`; it results in NaN where that would fail (a step of 0), and also if the
+// bounds are not finite or the index gets so large that adding `step` no longer changes it, since the summation would
+// never end in those cases
+fn eval_sigma_f64(from: f64, to: f64, step: f64, mut body: impl FnMut(f64) -> f64) -> f64 {
+    if step == 0.0 || step.is_nan() || !from.is_finite() || !to.is_finite() {
+        return f64::NAN;
+    }
+    let mut acc = 0.0;
+    let mut i = from;
+    while (step > 0.0 && i <= to) || (step < 0.0 && i >= to) {
+        acc += body(i);
+        if i + step == i {
+            return f64::NAN;
+        }
+        i += step;
+    }
+    acc
+}
+
+// Evaluates `expr` with all arithmetic done modulo `modulus`, so every intermediate result is in 0..modulus and
+// nothing can overflow; division is multiplication by the modular inverse of the divisor, so it fails if the divisor
+// has none (i.e. it isn't coprime with `modulus`). Moduli below 2 are refused. Only addition, subtraction,
+// multiplication, division, sums, products and If have a meaning modulo `modulus`; any other operator is an error.
+fn eval_mod(expr: &Expr, var: i64, modulus: i64) -> Option<i64> {
+    use Expr::*;
+    if modulus <= 1 {
+        return None;
+    }
+    // the operands are in 0..modulus, so this can be computed in an i128 without overflowing, and the result fits in
+    // an i64 again
+    let reduce = |value: i128| value.rem_euclid(modulus as i128) as i64;
+    let eval = |expr: &Expr| eval_mod(expr, var, modulus);
+    match expr {
+        Const(k) => Some(k.rem_euclid(modulus)),
+        Var => Some(var.rem_euclid(modulus)),
+        Add(lhs, rhs) => Some(reduce(eval(lhs)? as i128 + eval(rhs)? as i128)),
+        Sub(lhs, rhs) => Some(reduce(eval(lhs)? as i128 - eval(rhs)? as i128)),
+        Mul(lhs, rhs) => Some(reduce(eval(lhs)? as i128 * eval(rhs)? as i128)),
+        Div(lhs, rhs) => {
+            let inverse = mod_inverse(eval(rhs)?, modulus)?;
+            Some(reduce(eval(lhs)? as i128 * inverse as i128))
+        }
+        Summation(exprs) => exprs
+            .iter()
+            .try_fold(0, |acc, e| Some(reduce(acc as i128 + eval(e)? as i128))),
+        Product(exprs) => exprs
+            .iter()
+            .try_fold(1, |acc, e| Some(reduce(acc as i128 * eval(e)? as i128))),
+        If(cond, then, otherwise) => {
+            if eval(cond)? != 0 {
+                eval(then)
+            } else {
+                eval(otherwise)
+            }
+        }
+        _ => None,
+    }
+}
+
+// The number x in 0..modulus with a * x = 1 (modulo `modulus`), found with the extended Euclidean algorithm; None if
+// there is no such number, i.e. if a and `modulus` have a common divisor other than 1. `a` must be in 0..modulus.
+fn mod_inverse(a: i64, modulus: i64) -> Option<i64> {
+    // invariant: r == a * t (modulo `modulus`), for both (r, t) and (next_r, next_t)
+    let (mut r, mut next_r) = (modulus as i128, a as i128);
+    let (mut t, mut next_t) = (0i128, 1i128);
+    while next_r != 0 {
+        let q = r / next_r;
+        (r, next_r) = (next_r, r - q * next_r);
+        (t, next_t) = (next_t, t - q * next_t);
+    }
+    if r != 1 {
+        return None;
+    }
+    Some(t.rem_euclid(modulus as i128) as i64)
+}
+
+// The operators with two operands whose value is determined by the values of those operands alone (unlike e.g. If,
+// which only evaluates one of its branches), so that the evaluators of the different representations can share their
+// arithmetic
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Min,
+    Max,
+    Gcd,
+    Lcm,
+}
+
+impl BinaryOp {
+    fn apply(self, lhs: i64, rhs: i64) -> Option<i64> {
+        match self {
+            BinaryOp::Add => lhs.checked_add(rhs),
+            BinaryOp::Sub => lhs.checked_sub(rhs),
+            BinaryOp::Mul => lhs.checked_mul(rhs),
+            BinaryOp::Div => checked_div(lhs, rhs),
+            BinaryOp::Mod => checked_rem(lhs, rhs),
+            BinaryOp::Min => Some(lhs.min(rhs)),
+            BinaryOp::Max => Some(lhs.max(rhs)),
+            BinaryOp::Gcd => checked_gcd(lhs, rhs),
+            BinaryOp::Lcm => checked_lcm(lhs, rhs),
+        }
+    }
+}
+
+// The same for the operators with a single operand
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum UnaryOp {
+    // also for Sqrt, which `eval` can only round down as well
+    Isqrt,
+    Fact,
+}
+
+impl UnaryOp {
+    fn apply(self, operand: i64) -> Option<i64> {
+        match self {
+            // there is no (real) square root of a negative number
+            UnaryOp::Isqrt => operand.checked_isqrt(),
+            UnaryOp::Fact => checked_fact(operand),
+        }
+    }
+}
+
+// Division that fails instead of panicking, both when dividing by zero and when the result doesn't fit in an i64
+// (which only happens for i64::MIN / -1)
+fn checked_div(a: i64, b: i64) -> Option<i64> {
+    a.checked_div(b)
+}
+
+// The remainder that goes with `checked_div`; this only fails when dividing by zero, since i64::MIN % -1 is simply 0
+// (even though the quotient doesn't fit)
+fn checked_rem(a: i64, b: i64) -> Option<i64> {
+    match b {
+        0 => None,
+        -1 => Some(0),
+        _ => Some(a % b),
+    }
+}
+
+// `value` bounded to lo..=hi; this fails if the range is empty, i.e. if lo > hi (which `i64::clamp` would panic on)
+fn checked_clamp(value: i64, lo: i64, hi: i64) -> Option<i64> {
+    (lo <= hi).then(|| value.clamp(lo, hi))
+}
+
+// Euclid's algorithm; this is done on the absolute values as u64, since e.g. gcd(i64::MIN, 0) = 2^63 doesn't fit
+// in an i64 (in which case None is returned)
+fn checked_gcd(a: i64, b: i64) -> Option<i64> {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.try_into().ok()
+}
+
+// None if the result doesn't fit in an i64
+fn checked_lcm(a: i64, b: i64) -> Option<i64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / checked_gcd(a, b)?).checked_mul(b)?.checked_abs()
+}
+
+// None for negative numbers, and for 21 and up (whose factorial doesn't fit in an i64)
+fn checked_fact(n: i64) -> Option<i64> {
+    if n < 0 {
+        return None;
+    }
+    (2..=n).try_fold(1i64, |acc, i| acc.checked_mul(i))
+}
+
+// An expression is constant if its value doesn't depend on Var; note that the Var in the body of a Sigma refers to
+// the summation index instead, so it doesn't count.
+fn is_constant(expr: &Expr) -> bool {
+    use Expr::*;
+    match expr {
+        Const(_) => true,
+        Var => false,
+        Sigma(from, to, _) => is_constant(from) && is_constant(to),
+        SigmaStep(from, to, step, _) => is_constant(from) && is_constant(to) && is_constant(step),
+        _ => expr.operator().unwrap().1.into_iter().all(is_constant),
+    }
+}
+
+// Replaces every constant subexpression by its value, so that evaluating the result for many values of Var doesn't
+// compute these again and again. Constant subexpressions that fail to evaluate (e.g. divide by zero) are kept, so
+// the result still fails the same way.
+fn precompile(expr: &Expr) -> Expr {
+    if is_constant(expr) {
+        if let Some(k) = eval(expr, 0) {
+            return Const(k);
+        }
+    }
+    expr.map_children(precompile)
+}
+
+// Whether `expr` contains a division (or remainder) by a constant subexpression that is 0, which is certain to fail
+// if it is evaluated; divisors that depend on Var are never flagged, even if they are 0 for every value of Var.
+fn has_static_div_by_zero(expr: &Expr) -> bool {
+    use Expr::*;
+    if let Div(_, divisor) | Mod(_, divisor) = expr {
+        if is_constant(divisor) && eval(divisor, 0) == Some(0) {
+            return true;
+        }
+    }
+    expr.operator()
+        .is_some_and(|(_, operands)| operands.into_iter().any(has_static_div_by_zero))
+}
+
+// Counts the operations in `expr` by their kind, to estimate how costly it is to evaluate: a Summation of n operands
+// counts as n - 1 additions, and a Product as n - 1 multiplications. Every operation is counted once, even the ones
+// in the body of a Sigma (which is evaluated once for every value of the index).
+fn operation_histogram(expr: &Expr) -> std::collections::HashMap<&'static str, usize> {
+    let mut histogram = std::collections::HashMap::new();
+    count_operations(expr, &mut histogram);
+    histogram
+}
+
+fn count_operations(expr: &Expr, histogram: &mut std::collections::HashMap<&'static str, usize>) {
+    use Expr::*;
+    let (kind, count) = match expr {
+        Const(_) | Var => return,
+        Add(..) => ("add", 1),
+        Sub(..) => ("sub", 1),
+        Mul(..) => ("mul", 1),
+        Div(..) => ("div", 1),
+        Mod(..) => ("mod", 1),
+        Summation(exprs) => ("add", exprs.len().saturating_sub(1)),
+        Product(exprs) => ("mul", exprs.len().saturating_sub(1)),
+        Sigma(..) => ("sigma", 1),
+        SigmaStep(..) => ("sigma-step", 1),
+        If(..) => ("if", 1),
+        Min(..) => ("min", 1),
+        Max(..) => ("max", 1),
+        Clamp(..) => ("clamp", 1),
+        Isqrt(..) => ("isqrt", 1),
+        Sqrt(..) => ("sqrt", 1),
+        Gcd(..) => ("gcd", 1),
+        Lcm(..) => ("lcm", 1),
+        Fact(..) => ("fact", 1),
+        Call(..) => ("call", 1),
+    };
+    if count > 0 {
+        *histogram.entry(kind).or_default() += count;
+    }
+    for operand in expr.operator().unwrap().1 {
+        count_operations(operand, histogram);
+    }
+}
+
+const MAX_EXPANDED_SIGMA_TERMS: i64 = 10;
+
+// Simplifies an expression without changing its value (or whether it fails): constant subexpressions are folded like
+// in `precompile`, and additions of 0 and multiplications or divisions by 1 are dropped. An If with a constant
+// condition is replaced by the branch it takes, and a Sigma with constant bounds (that failed to fold, since it would
+// otherwise be constant) is expanded into a Summation like in `expand_sigma`, if it has at most
+// `MAX_EXPANDED_SIGMA_TERMS` terms.
+fn simplify(expr: &Expr) -> Expr {
+    use Expr::*;
+    if is_constant(expr) {
+        if let Some(k) = eval(expr, 0) {
+            return Const(k);
+        }
+    }
+    match expr.map_children(simplify) {
+        Add(lhs, rhs) if *lhs == Const(0) => *rhs,
+        Add(lhs, rhs) | Sub(lhs, rhs) if *rhs == Const(0) => *lhs,
+        Mul(lhs, rhs) if *lhs == Const(1) => *rhs,
+        Mul(lhs, rhs) | Div(lhs, rhs) if *rhs == Const(1) => *lhs,
+        Summation(exprs) => {
+            let mut exprs: Vec<Expr> = exprs.into_iter().filter(|e| *e != Const(0)).collect();
+            match exprs.len() {
+                0 => Const(0),
+                1 => exprs.pop().unwrap(),
+                _ => Summation(exprs),
+            }
+        }
+        Product(exprs) => {
+            let mut exprs: Vec<Expr> = exprs.into_iter().filter(|e| *e != Const(1)).collect();
+            match exprs.len() {
+                0 => Const(1),
+                1 => exprs.pop().unwrap(),
+                _ => Product(exprs),
+            }
+        }
+        If(cond, then, otherwise) if matches!(*cond, Const(_)) => {
+            if *cond != Const(0) {
+                *then
+            } else {
+                *otherwise
+            }
+        }
+        Sigma(from, to, body) => match (&*from, &*to) {
+            (&Const(from), &Const(to)) if to.saturating_sub(from) < MAX_EXPANDED_SIGMA_TERMS => {
+                simplify(&Summation(
+                    (from..=to).map(|i| substitute(&body, i)).collect(),
+                ))
+            }
+            _ => Sigma(from, to, body),
+        },
+        simplified => simplified,
+    }
+}
+
+// Rewrites all nested additions and Summations into a single Summation of their terms, and likewise all nested
+// multiplications and Products into a single Product. The value of the expression stays the same, except that
+// regrouping the operands may change whether an intermediate result overflows.
+fn flatten(expr: Expr) -> Expr {
+    flattened(&expr)
+}
+
+fn flattened(expr: &Expr) -> Expr {
+    use Expr::*;
+    fn terms(expr: &Expr, acc: &mut Vec<Expr>) {
+        match expr {
+            Add(lhs, rhs) => {
+                terms(lhs, acc);
+                terms(rhs, acc);
+            }
+            Summation(exprs) => exprs.iter().for_each(|e| terms(e, acc)),
+            _ => acc.push(flattened(expr)),
+        }
+    }
+    fn factors(expr: &Expr, acc: &mut Vec<Expr>) {
+        match expr {
+            Mul(lhs, rhs) => {
+                factors(lhs, acc);
+                factors(rhs, acc);
+            }
+            Product(exprs) => exprs.iter().for_each(|e| factors(e, acc)),
+            _ => acc.push(flattened(expr)),
+        }
+    }
+
+    let mut acc = Vec::new();
+    match expr {
+        Add(..) | Summation(..) => {
+            terms(expr, &mut acc);
+            Summation(acc)
+        }
+        Mul(..) | Product(..) => {
+            factors(expr, &mut acc);
+            Product(acc)
+        }
+        _ => expr.map_children(flattened),
+    }
+}
+
+// Two expressions are equivalent if they are equal up to the order of the operands of additions and multiplications
+// (not for subtractions, divisions, etc.); nested additions/summations and nested multiplications/products are
+// considered as single sums and products, so the grouping of their operands doesn't matter either.
+fn equivalent(a: &Expr, b: &Expr) -> bool {
+    canonical(&flattened(a)) == canonical(&flattened(b))
+}
+
+// sorts the operands of every (flattened) Summation and Product, and replaces those with a single operand by it
+fn canonical(expr: &Expr) -> Expr {
+    match expr.map_children(canonical) {
+        Summation(mut exprs) | Product(mut exprs) if exprs.len() == 1 => exprs.pop().unwrap(),
+        Summation(mut exprs) => {
+            exprs.sort();
+            Summation(exprs)
+        }
+        Product(mut exprs) => {
+            exprs.sort();
+            Product(exprs)
+        }
+        canonical => canonical,
+    }
+}
+
+// Rewrites every Sigma whose bounds are constant into an explicit Summation of its body, with Var replaced by each
+// value of the summation index; e.g. `sigma(1, 3, (x * x))` becomes `sum((1 * 1), (2 * 2), (3 * 3))`. Sigmas whose
+// bounds depend on Var (or fail to evaluate) are left unchanged. Note that the Summation has an operand for every
+// value of the index, so this is only sensible for small ranges.
+fn expand_sigma(expr: Expr) -> Expr {
+    expanded(&expr)
+}
+
+fn expanded(expr: &Expr) -> Expr {
+    use Expr::*;
+    if let Sigma(from, to, body) = expr {
+        if let (Some(from), Some(to)) = (constant_value(from), constant_value(to)) {
+            // the body may contain Sigmas whose bounds only just became constant
+            return Summation(
+                (from..=to)
+                    .map(|i| expanded(&substitute(body, i)))
+                    .collect(),
+            );
+        }
     }
+    expr.map_children(expanded)
+}
+
+fn constant_value(expr: &Expr) -> Option<i64> {
+    if is_constant(expr) {
+        eval(expr, 0)
+    } else {
+        None
+    }
+}
+
+// replaces Var by Const(value), except where it refers to the index of a Sigma in `expr`
+fn substitute(expr: &Expr, value: i64) -> Expr {
+    use Expr::*;
+    let sub = |e: &Expr| Box::new(substitute(e, value));
+    match expr {
+        Var => Const(value),
+        Sigma(from, to, body) => Sigma(sub(from), sub(to), body.clone()),
+        SigmaStep(from, to, step, body) => SigmaStep(sub(from), sub(to), sub(step), body.clone()),
+        _ => expr.map_children(|e| substitute(e, value)),
+    }
+}
+
+// The ways in which evaluating an expression with `eval_bounded` can fail
+#[derive(PartialEq, Debug)]
+enum EvalError {
+    // the expression is nested more deeply than allowed
+    TooDeep,
+    // the value is undefined (e.g. a division by zero) or doesn't fit in an i64; i.e. `eval` returns None
+    Undefined,
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::TooDeep => write!(f, "expression is nested too deeply"),
+            EvalError::Undefined => write!(f, "expression has no value"),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+// Like `eval`, but refuses expressions that are more than `max_depth` levels deep (where Const and Var are one level)
+// instead of risking a stack overflow; e.g. for expressions that come from untrusted input.
+fn eval_bounded(expr: &Expr, var: i64, max_depth: usize) -> Result<i64, EvalError> {
+    if deeper_than(expr, max_depth) {
+        return Err(EvalError::TooDeep);
+    }
+    eval(expr, var).ok_or(EvalError::Undefined)
+}
+
+// whether `expr` is more than `max_depth` levels deep; this never recurses more than `max_depth` levels itself
+fn deeper_than(expr: &Expr, max_depth: usize) -> bool {
+    match (max_depth, expr.operator()) {
+        (0, _) => true,
+        (_, None) => false,
+        (_, Some((_, operands))) => operands.into_iter().any(|e| deeper_than(e, max_depth - 1)),
+    }
+}
+
+// The ways in which parsing one of the textual formats of an expression can fail; positions are byte offsets into the
+// input
+#[derive(PartialEq, Debug)]
+enum ParseError {
+    // something that is not a number, `x` or the name of an operator
+    UnknownToken { token: String, position: usize },
+    // an operator that is given fewer operands than it takes
+    MissingOperand { operator: String, position: usize },
+    // input that remains after a complete expression
+    TrailingInput { position: usize },
+    // a parenthesis without a partner
+    UnmatchedParen { position: usize },
+    // the input ended before a complete expression was read
+    UnexpectedEnd,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnknownToken { token, position } => {
+                write!(f, "unknown token '{token}' at position {position}")
+            }
+            ParseError::MissingOperand { operator, position } => {
+                write!(f, "missing operand for '{operator}' at position {position}")
+            }
+            ParseError::TrailingInput { position } => {
+                write!(
+                    f,
+                    "unexpected input after the expression at position {position}"
+                )
+            }
+            ParseError::UnmatchedParen { position } => {
+                write!(f, "unmatched parenthesis at position {position}")
+            }
+            ParseError::UnexpectedEnd => write!(f, "unexpected end of input"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// splits `s` into its whitespace-separated tokens, along with their positions
+fn tokens(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    s.split_whitespace()
+        .map(move |token| (token.as_ptr() as usize - s.as_ptr() as usize, token))
+}
+
+// The name of a Call can be any string, so the text formats write it as a single token: every whitespace character,
+// parenthesis and `%` in it is replaced by a `%` and two hex digits for each of its UTF-8 bytes (e.g. `%20` for a
+// space), and the empty name is written as a lone `%`.
+fn escape_name(name: &str) -> String {
+    if name.is_empty() {
+        return "%".to_string();
+    }
+    let mut out = String::new();
+    for c in name.chars() {
+        if c.is_whitespace() || matches!(c, '(' | ')' | '%') {
+            for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                out.push_str(&format!("%{byte:02X}"));
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// the inverse of `escape_name`; this fails if a `%` isn't followed by two hex digits, or if these don't make up valid
+// UTF-8
+fn unescape_name(token: &str) -> Option<String> {
+    match token {
+        "" => return None,
+        "%" => return Some(String::new()),
+        _ => {}
+    }
+    let mut bytes = Vec::new();
+    let mut rest = token.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail
+                .get(..2)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+            bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
 }
 
 fn main() {
     let test = |expr| {
         let value = rand::random::<i8>() as i64;
         println!(
-            "{:?} with Var = {} ==> {}",
+            "{:?} with Var = {} ==> {:?}",
             &expr,
             value,
             eval(&expr, value)
@@ -81,6 +1201,79 @@ fn main() {
     test(sub(Var, Var));
     test(add(sub(Var, Const(5)), Const(5)));
     test(Summation(vec![Var, Const(1)]));
+
+    // the other evaluators, rewrites and text formats, on an expression that has every kind of node
+    let expr = Summation(vec![
+        builder::ExprBuilder::var()
+            .add(1)
+            .sub(2)
+            .mul(3)
+            .div(4)
+            .rem(5)
+            .min(6)
+            .max(-6)
+            .gcd(builder::ExprBuilder::new(7))
+            .lcm(builder::ExprBuilder::constant(8))
+            .isqrt()
+            .build(),
+        sigma(Const(1), Var, mul(Var, Var)),
+        Product(vec![
+            sigma_step(Const(0), Const(4), Const(2), Var),
+            rem(Var, Const(3)),
+        ]),
+        if_then_else(Var, clamp(sqrt(Var), Const(0), Const(3)), fact(Const(4))),
+        min(
+            div(gcd(Var, Const(6)), Const(2)),
+            max(lcm(Var, Const(4)), isqrt(Const(10))),
+        ),
+    ]);
+    println!("{expr}\n{}", to_tree_string(&expr));
+    let (value, trace) = eval_trace(&expr, 5);
+    println!(
+        "with Var = 5 ==> {value:?}, from {} subexpressions",
+        trace.len()
+    );
+    println!(
+        "saturating: {}, in f64: {}, modulo 7: {:?}, at most 3 deep: {:?}",
+        eval_saturating(&expr, 5),
+        eval_f64(&expr, 5.0),
+        eval_mod(&expr, 5, 7),
+        eval_bounded(&expr, 5, 3)
+    );
+    println!("with Var = 0..=5 ==> {:?}", eval_range(&expr, 0, 5));
+    println!("operations: {:?}", operation_histogram(&expr));
+
+    let simplified = simplify(&expr);
+    println!(
+        "simplified: {simplified}, equivalent: {}",
+        equivalent(&expr, &simplified)
+    );
+    println!("flattened: {}", flatten(expr.clone()));
+    let constant = substitute(&expr, 5);
+    println!(
+        "with Var = 5: {}, which expands to {} and has value {:?}",
+        constant,
+        expand_sigma(constant.clone()),
+        constant_value(&constant)
+    );
+    println!("divides by zero: {}", has_static_div_by_zero(&expr));
+
+    let rpn = rpn::to_rpn(&expr);
+    assert_eq!(rpn::from_rpn(&rpn), Ok(expr.clone()));
+    let sexpr = sexpr::to_sexpr(&expr);
+    assert_eq!(sexpr::from_sexpr(&sexpr), Ok(expr.clone()));
+    println!("in RPN: {rpn}\nas an S-expression: {sexpr}");
+    let call = Expr::Call("max".to_string(), vec![expr.clone(), Const(100)]);
+    let mut fns = Functions::new();
+    fns.insert("max".to_string(), |args| args.iter().copied().max());
+    println!("{call} ==> {:?}", eval_with_fns(&call, 5, &fns));
+    let (arena, root) = arena::from_expr(&expr);
+    assert!(!arena.is_empty());
+    println!(
+        "in an arena of {} nodes ==> {:?}",
+        arena.len(),
+        arena::eval_arena(&arena, root, 5)
+    );
 }
 
 #[cfg(test)]
@@ -90,12 +1283,688 @@ mod test {
     #[test]
     fn test_cases() {
         let x = 42;
-        assert_eq!(eval(&Const(5), x), 5);
-        assert_eq!(eval(&Var, x), 42);
-        assert_eq!(eval(&sub(Var, Const(5)), x), 37);
-        assert_eq!(eval(&sub(Var, Var), x), 0);
-        assert_eq!(eval(&add(sub(Var, Const(5)), Const(5)), x), 42);
-        assert_eq!(eval(&Summation(vec![Var, Const(1)]), x), 43);
+        assert_eq!(eval(&Const(5), x), Some(5));
+        assert_eq!(eval(&Var, x), Some(42));
+        assert_eq!(eval(&sub(Var, Const(5)), x), Some(37));
+        assert_eq!(eval(&sub(Var, Var), x), Some(0));
+        assert_eq!(eval(&add(sub(Var, Const(5)), Const(5)), x), Some(42));
+        assert_eq!(eval(&Summation(vec![Var, Const(1)]), x), Some(43));
+        assert_eq!(eval(&mul(Var, Const(2)), x), Some(84));
+        assert_eq!(eval(&div(Var, Const(5)), x), Some(8));
+        assert_eq!(eval(&div(Var, sub(Var, Var)), x), None);
+        assert_eq!(eval(&add(Const(1), div(Var, Const(0))), x), None);
+    }
+
+    #[test]
+    fn test_sigma() {
+        let x = 42;
+        let expanded = Summation(vec![Const(1), Const(2), Const(3), Const(4), Const(5)]);
+        assert_eq!(eval(&sigma(Const(1), Const(5), Var), x), eval(&expanded, x));
+        assert_eq!(eval(&sigma(Const(5), Const(1), Var), x), Some(0));
+        assert_eq!(
+            eval(&sigma(Const(1), Const(3), div(Var, Const(0))), x),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sigma_step() {
+        let x = 42;
+        let evens = sigma_step(Const(0), Const(10), Const(2), Var);
+        assert_eq!(eval(&evens, x), Some(2 + 4 + 6 + 8 + 10));
+        let odds = sigma_step(Const(1), Const(10), Const(2), Var);
+        assert_eq!(eval(&odds, x), Some(1 + 3 + 5 + 7 + 9));
+
+        let countdown = sigma_step(Const(10), Const(0), Const(-3), Var);
+        assert_eq!(eval(&countdown, x), Some(10 + 7 + 4 + 1));
+        assert_eq!(
+            eval(&sigma_step(Const(0), Const(10), Const(-1), Var), x),
+            Some(0)
+        );
+
+        assert_eq!(
+            eval(&sigma_step(Const(0), Const(10), Const(0), Var), x),
+            None
+        );
+        assert_eq!(
+            eval(&sigma_step(Const(0), Const(0), sub(Var, Var), Var), x),
+            None
+        );
+    }
+
+    #[test]
+    fn test_if() {
+        assert_eq!(
+            eval(&if_then_else(Const(1), Const(2), Const(3)), 0),
+            Some(2)
+        );
+        assert_eq!(
+            eval(&if_then_else(Const(0), Const(2), Const(3)), 0),
+            Some(3)
+        );
+        assert_eq!(
+            eval(&if_then_else(Const(-7), Const(2), Const(3)), 0),
+            Some(2)
+        );
+
+        // only the branch that is taken may fail
+        let safe_div = if_then_else(Var, div(Const(10), Var), Const(0));
+        assert_eq!(eval(&safe_div, 5), Some(2));
+        assert_eq!(eval(&safe_div, 0), Some(0));
+        let unsafe_div = if_then_else(Var, Const(0), div(Const(10), Var));
+        assert_eq!(eval(&unsafe_div, 0), None);
+        assert_eq!(
+            eval(&if_then_else(div(Var, Const(0)), Const(1), Const(2)), 0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_min_max() {
+        let relu = max(Var, Const(0));
+        assert_eq!(eval(&relu, 7), Some(7));
+        assert_eq!(eval(&relu, -7), Some(0));
+        assert_eq!(eval(&min(Var, Const(0)), 7), Some(0));
+        assert_eq!(eval(&min(Var, Const(0)), -7), Some(-7));
+
+        assert_eq!(eval(&max(Var, div(Var, Const(0))), 1), None);
+        assert_eq!(eval(&min(div(Var, Const(0)), Var), 1), None);
+    }
+
+    #[test]
+    fn test_isqrt() {
+        assert_eq!(eval(&isqrt(Const(16)), 0), Some(4));
+        assert_eq!(eval(&isqrt(Const(17)), 0), Some(4));
+        assert_eq!(eval(&isqrt(Const(0)), 0), Some(0));
+        assert_eq!(eval(&isqrt(Const(-1)), 0), None);
+        assert_eq!(eval(&isqrt(Var), i64::MAX), Some(3_037_000_499));
+        assert_eq!(
+            eval(&isqrt(Var), 3_037_000_499 * 3_037_000_499),
+            Some(3_037_000_499)
+        );
+        assert_eq!(
+            eval(&isqrt(Var), 3_037_000_499 * 3_037_000_499 - 1),
+            Some(3_037_000_498)
+        );
+    }
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(eval(&gcd(Const(12), Const(18)), 0), Some(6));
+        assert_eq!(eval(&gcd(Const(-12), Const(18)), 0), Some(6));
+        assert_eq!(eval(&gcd(Var, Const(0)), 7), Some(7));
+        assert_eq!(eval(&gcd(Const(0), Const(0)), 0), Some(0));
+        assert_eq!(eval(&gcd(Var, Const(0)), i64::MIN), None);
+
+        assert_eq!(eval(&lcm(Const(4), Const(6)), 0), Some(12));
+        assert_eq!(eval(&lcm(Const(-4), Const(6)), 0), Some(12));
+        assert_eq!(eval(&lcm(Var, Const(0)), 7), Some(0));
+        assert_eq!(eval(&lcm(Var, sub(Var, Const(1))), i64::MAX), None);
+    }
+
+    #[test]
+    fn test_fact() {
+        assert_eq!(eval(&fact(Const(5)), 0), Some(120));
+        assert_eq!(eval(&fact(Var), 0), Some(1));
+        assert_eq!(eval(&fact(Var), 20), Some(2_432_902_008_176_640_000));
+        assert_eq!(eval(&fact(Const(-1)), 0), None);
+        assert_eq!(eval(&fact(Var), 21), None);
+        assert_eq!(eval(&fact(Const(25)), 0), None);
+    }
+
+    #[test]
+    fn test_eval_mod() {
+        // 3x^2 + 2x - 5
+        let poly = sub(
+            add(mul(Const(3), mul(Var, Var)), mul(Const(2), Var)),
+            Const(5),
+        );
+        for var in [-20, -1, 0, 1, 6, 12, 1000] {
+            assert_eq!(
+                eval_mod(&poly, var, 7),
+                Some(eval(&poly, var).unwrap().rem_euclid(7))
+            );
+        }
+        // no overflow happens, since everything stays small
+        assert_eq!(
+            eval_mod(&mul(Var, Var), i64::MAX, 1_000_000_007),
+            Some(i64::MAX.rem_euclid(1_000_000_007).pow(2) % 1_000_000_007)
+        );
+
+        // 3 * 5 = 1 (modulo 7), so dividing by 3 is multiplying by 5
+        assert_eq!(eval_mod(&div(Const(1), Const(3)), 0, 7), Some(5));
+        assert_eq!(eval_mod(&div(Const(2), Const(3)), 0, 7), Some(3));
+        assert_eq!(eval_mod(&div(Var, Var), 5, 7), Some(1));
+        // but divisors that share a factor with the modulus have no inverse
+        assert_eq!(eval_mod(&div(Const(1), Const(4)), 0, 6), None);
+        assert_eq!(eval_mod(&div(Const(1), Var), 7, 7), None);
+
+        assert_eq!(eval_mod(&Var, 3, 1), None);
+        assert_eq!(eval_mod(&isqrt(Var), 3, 7), None);
+    }
+
+    #[test]
+    fn test_eval_f64() {
+        assert!((eval_f64(&sqrt(Const(2)), 0.0) - 1.414).abs() < 0.001);
+        assert_eq!(eval_f64(&add(mul(Var, Const(3)), Const(2)), 0.5), 3.5);
+        assert_eq!(eval_f64(&div(Const(1), Const(4)), 0.0), 0.25);
+
+        // dividing by zero doesn't fail (nor panic), but gives an infinite value
+        assert_eq!(eval_f64(&div(Const(1), Var), 0.0), f64::INFINITY);
+        assert_eq!(eval_f64(&div(Const(-1), Var), 0.0), f64::NEG_INFINITY);
+        assert!(eval_f64(&div(Var, Var), 0.0).is_nan());
+        assert!(eval_f64(&sqrt(Const(-1)), 0.0).is_nan());
+
+        assert_eq!(eval_f64(&sigma(Const(1), Var, Var), 4.5), 10.0);
+        assert!(eval_f64(&sigma_step(Const(1), Var, Const(0), Var), 4.0).is_nan());
+        assert!(eval_f64(&sigma(Const(1), div(Const(1), Const(0)), Var), 0.0).is_nan());
+
+        assert_eq!(eval_f64(&gcd(Const(-12), Const(18)), 0.0), 6.0);
+        assert_eq!(eval_f64(&lcm(Const(4), Var), 6.0), 12.0);
+        assert!(eval_f64(&gcd(Const(12), Var), 1.5).is_nan());
+        assert_eq!(eval_f64(&fact(Var), 5.0), 120.0);
+        assert_eq!(eval_f64(&fact(Var), 1000.0), f64::INFINITY);
+        assert!(eval_f64(&fact(Var), -1.0).is_nan());
+        assert_eq!(eval_f64(&isqrt(Var), 17.0), 4.0);
+    }
+
+    #[test]
+    fn test_to_tree_string() {
+        assert_eq!(to_tree_string(&Var), "x");
+        let expr = mul(
+            Var,
+            Summation(vec![Const(1), add(Var, Const(-2)), Summation(vec![])]),
+        );
+        let expected = "\
+*
+  x
+  sum
+    1
+    +
+      x
+      -2
+    sum";
+        assert_eq!(to_tree_string(&expr), expected);
+    }
+
+    #[test]
+    fn test_operation_histogram() {
+        let histogram = operation_histogram(&add(mul(Var, Const(2)), Const(3)));
+        assert_eq!(histogram, [("add", 1), ("mul", 1)].into());
+
+        let expr = sigma(
+            Const(1),
+            Var,
+            Summation(vec![Var, mul(Var, Var), div(Var, Const(2)), Const(1)]),
+        );
+        let histogram = operation_histogram(&expr);
+        assert_eq!(
+            histogram,
+            [("sigma", 1), ("add", 3), ("mul", 1), ("div", 1)].into()
+        );
+
+        // the same name as in the textual formats
+        let histogram = operation_histogram(&sigma_step(Const(0), Var, Const(2), Var));
+        assert_eq!(histogram, [("sigma-step", 1)].into());
+
+        assert!(operation_histogram(&Summation(vec![Var])).is_empty());
+        assert!(operation_histogram(&Const(1)).is_empty());
+    }
+
+    #[test]
+    fn test_eval_range() {
+        assert_eq!(
+            eval_range(&mul(Var, Var), 0, 3),
+            [Some(0), Some(1), Some(4), Some(9)]
+        );
+        assert_eq!(
+            eval_range(&div(Const(6), Var), -1, 1),
+            [Some(-6), None, Some(6)]
+        );
+        assert_eq!(eval_range(&Var, 1, 0), []);
+    }
+
+    #[test]
+    fn test_eval_saturating() {
+        assert_eq!(eval_saturating(&add(mul(Var, Const(3)), Const(2)), 5), 17);
+        assert_eq!(eval_saturating(&add(Var, Const(1)), i64::MAX), i64::MAX);
+        assert_eq!(eval_saturating(&sub(Var, Const(1)), i64::MIN), i64::MIN);
+        assert_eq!(eval_saturating(&mul(Var, Var), -1 << 40), i64::MAX);
+        assert_eq!(eval_saturating(&mul(Var, Const(-2)), i64::MAX), i64::MIN);
+        assert_eq!(eval_saturating(&div(Var, Const(-1)), i64::MIN), i64::MAX);
+
+        // dividing by zero saturates toward the sign of the numerator
+        assert_eq!(eval_saturating(&div(Var, Const(0)), 7), i64::MAX);
+        assert_eq!(eval_saturating(&div(Var, Const(0)), -7), i64::MIN);
+        assert_eq!(eval_saturating(&div(Var, Const(0)), 0), 0);
+        assert_eq!(eval_saturating(&rem(Var, Const(0)), 7), 0);
+
+        assert_eq!(eval_saturating(&sigma(Const(1), Var, Var), 4), 10);
+        assert_eq!(
+            eval_saturating(&sigma(Const(1), Const(3), Const(i64::MAX)), 0),
+            i64::MAX
+        );
+        assert_eq!(
+            eval_saturating(&sigma_step(Const(1), Var, Const(0), Var), 4),
+            0
+        );
+        // a huge range doesn't take forever: if every term is the same, they are multiplied
+        assert_eq!(
+            eval_saturating(&sigma(Const(0), Const(i64::MAX), Const(1)), 0),
+            i64::MAX
+        );
+        assert_eq!(
+            eval_saturating(
+                &sigma_step(Const(0), Const(i64::MAX), Const(1 << 32), Const(-3)),
+                0
+            ),
+            -3 << 31
+        );
+        assert_eq!(
+            eval_saturating(
+                &sigma(Const(0), Const(i64::MAX), sigma(Const(1), Const(2), Var)),
+                0
+            ),
+            i64::MAX
+        );
+        // and otherwise only the first terms count (of all the Sigmas together)
+        assert_eq!(
+            eval_saturating(&sigma(Const(i64::MIN), Const(i64::MAX), Var), 0),
+            i64::MIN
+        );
+        let nested = sigma(Const(0), Const(i64::MAX), sigma(Const(0), Var, Const(1)));
+        let n = SATURATING_SIGMA_BUDGET as i64;
+        assert_eq!(eval_saturating(&nested, 0), n * (n + 1) / 2);
+        assert_eq!(eval_saturating(&isqrt(Var), -4), 0);
+        assert_eq!(eval_saturating(&fact(Var), 25), i64::MAX);
+        assert_eq!(eval_saturating(&fact(Var), -1), 0);
+        assert_eq!(eval_saturating(&gcd(Var, Const(0)), i64::MIN), i64::MAX);
+
+        // where `eval` succeeds, the result is the same
+        let expr = lcm(sub(Var, Const(3)), rem(Var, Const(4)));
+        for var in -10..10 {
+            assert_eq!(Some(eval_saturating(&expr, var)), eval(&expr, var));
+        }
+    }
+
+    #[test]
+    fn test_eval_with_fns() {
+        let mut fns = Functions::new();
+        fns.insert("max".to_string(), |args| match args {
+            [a, b] => Some(*a.max(b)),
+            _ => None,
+        });
+        let call = Expr::Call("max".to_string(), vec![Var, Const(3)]);
+        assert_eq!(eval_with_fns(&call, 1, &fns), Some(3));
+        assert_eq!(eval_with_fns(&call, 7, &fns), Some(7));
+        assert_eq!(
+            eval_with_fns(&add(call.clone(), Const(1)), 7, &fns),
+            Some(8)
+        );
+        assert_eq!(call.to_string(), "call:max(x, 3)");
+        // a function may have the name of an operator, but it's never written like one
+        let call = Expr::Call("+".to_string(), vec![Var, Const(1)]);
+        assert_eq!(call.to_string(), "call:+(x, 1)");
+        assert_ne!(call.to_string(), add(Var, Const(1)).to_string());
+        let call = Expr::Call("min".to_string(), vec![Var, Const(1)]);
+        assert_ne!(call.to_string(), min(Var, Const(1)).to_string());
+        assert_eq!(to_tree_string(&call), "call:min\n  x\n  1");
+
+        // the wrong number of arguments
+        let call = Expr::Call("max".to_string(), vec![Var]);
+        assert_eq!(eval_with_fns(&call, 1, &fns), None);
+        // an unknown function
+        let call = Expr::Call("min".to_string(), vec![Var, Const(3)]);
+        assert_eq!(eval_with_fns(&call, 1, &fns), None);
+        // a failing argument
+        let call = Expr::Call("max".to_string(), vec![Var, div(Var, Const(0))]);
+        assert_eq!(eval_with_fns(&call, 1, &fns), None);
+        // `eval` knows no functions at all
+        assert_eq!(
+            eval(&Expr::Call("max".to_string(), vec![Var, Const(3)]), 1),
+            None
+        );
+    }
+
+    #[test]
+    fn test_eval_clamp() {
+        let expr = clamp(Var, Const(-2), Const(5));
+        assert_eq!(eval(&expr, -10), Some(-2));
+        assert_eq!(eval(&expr, 3), Some(3));
+        assert_eq!(eval(&expr, 10), Some(5));
+        assert_eq!(expr.to_string(), "clamp(x, -2, 5)");
+        // a range with a single value is fine, but an empty one isn't
+        assert_eq!(eval(&clamp(Var, Const(1), Const(1)), 10), Some(1));
+        assert_eq!(eval(&clamp(Var, Const(5), Const(-2)), 3), None);
+
+        assert_eq!(eval_saturating(&clamp(Var, Const(5), Const(-2)), 3), 0);
+        assert_eq!(eval_f64(&clamp(Var, Const(-2), Const(5)), 2.5), 2.5);
+        assert!(eval_f64(&clamp(Var, Const(5), Const(-2)), 2.5).is_nan());
+        assert!(eval_f64(&clamp(Var, Const(0), div(Var, Var)), 0.0).is_nan());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            add(mul(Var, Const(3)), Const(-2)).to_string(),
+            "((x * 3) + -2)"
+        );
+        assert_eq!(
+            sigma(Const(1), Var, rem(Var, Const(2))).to_string(),
+            "sigma(1, x, (x % 2))"
+        );
+        assert_eq!(Summation(vec![]).to_string(), "sum()");
+        assert_eq!(
+            if_then_else(Var, fact(Var), Const(0)).to_string(),
+            "if(x, fact(x), 0)"
+        );
+    }
+
+    #[test]
+    fn test_eval_trace() {
+        let expr = add(mul(Var, Const(3)), Const(2));
+        let (value, trace) = eval_trace(&expr, 5);
+        assert_eq!(value, Some(17));
+        let expected = [
+            ("x", Some(5)),
+            ("3", Some(3)),
+            ("(x * 3)", Some(15)),
+            ("2", Some(2)),
+            ("((x * 3) + 2)", Some(17)),
+        ];
+        assert_eq!(trace, expected.map(|(s, v)| (s.to_string(), v)));
+
+        // the failing division is traced, but the multiplication after it is never evaluated
+        let expr = sub(div(Var, Const(0)), mul(Var, Var));
+        let (value, trace) = eval_trace(&expr, 5);
+        assert_eq!(value, None);
+        let expected = [
+            ("x", Some(5)),
+            ("0", Some(0)),
+            ("(x / 0)", None),
+            ("((x / 0) - (x * x))", None),
+        ];
+        assert_eq!(trace, expected.map(|(s, v)| (s.to_string(), v)));
+
+        let expr = sigma(Const(1), Var, Var);
+        let (value, trace) = eval_trace(&expr, 3);
+        assert_eq!(value, eval(&expr, 3));
+        let values: Vec<_> = trace.into_iter().map(|(_, value)| value.unwrap()).collect();
+        assert_eq!(values, [1, 3, 1, 2, 3, 6]);
+
+        // a Summation stops at the first operand that makes it overflow
+        let expr = Summation(vec![Const(i64::MAX), Const(1), Var]);
+        let (value, trace) = eval_trace(&expr, 5);
+        assert_eq!(value, None);
+        let traced: Vec<_> = trace.into_iter().map(|(s, _)| s).collect();
+        assert_eq!(traced, [&i64::MAX.to_string(), "1", &expr.to_string()]);
+    }
+
+    #[test]
+    fn test_expand_sigma() {
+        assert_eq!(
+            expand_sigma(sigma(Const(1), Const(3), Var)),
+            Summation(vec![Const(1), Const(2), Const(3)])
+        );
+        assert_eq!(
+            expand_sigma(add(Var, sigma(Const(1), Const(2), mul(Var, Var)))),
+            add(
+                Var,
+                Summation(vec![mul(Const(1), Const(1)), mul(Const(2), Const(2))])
+            )
+        );
+        // the bounds of an inner Sigma may refer to the index of an outer one
+        let nested = sigma(Const(1), Const(2), sigma(Const(0), Var, Var));
+        assert_eq!(
+            expand_sigma(nested.clone()),
+            Summation(vec![
+                Summation(vec![Const(0), Const(1)]),
+                Summation(vec![Const(0), Const(1), Const(2)])
+            ])
+        );
+        assert_eq!(eval(&expand_sigma(nested.clone()), 0), eval(&nested, 0));
+
+        assert_eq!(
+            expand_sigma(sigma(Const(1), Const(0), Var)),
+            Summation(vec![])
+        );
+        let unbounded = sigma(Const(1), Var, Var);
+        assert_eq!(expand_sigma(unbounded.clone()), unbounded);
+    }
+
+    #[test]
+    fn test_equivalent() {
+        assert!(equivalent(&add(Var, Const(1)), &add(Const(1), Var)));
+        assert!(equivalent(&mul(Var, Const(2)), &mul(Const(2), Var)));
+        assert!(equivalent(
+            &add(add(Var, Const(1)), Const(2)),
+            &add(Const(2), add(Const(1), Var))
+        ));
+        assert!(equivalent(
+            &Summation(vec![Var, Const(1), Const(2)]),
+            &add(Const(2), add(Const(1), Var))
+        ));
+        assert!(equivalent(
+            &mul(mul(Var, Const(2)), Const(3)),
+            &mul(Const(3), mul(Var, Const(2)))
+        ));
+        // the operands of nested expressions are compared up to commutativity as well
+        assert!(equivalent(
+            &sub(add(Var, Const(1)), mul(Var, Const(3))),
+            &sub(add(Const(1), Var), mul(Const(3), Var))
+        ));
+
+        let (a, b) = (add(Var, Const(1)), Const(2));
+        assert!(!equivalent(
+            &sub(a.clone(), b.clone()),
+            &sub(b.clone(), a.clone())
+        ));
+        assert!(!equivalent(&div(a.clone(), b.clone()), &div(b, a)));
+        assert!(!equivalent(&add(Var, Const(1)), &mul(Var, Const(1))));
+        assert!(!equivalent(&add(Var, Var), &add(Var, Const(1))));
+    }
+
+    #[test]
+    fn test_flatten() {
+        assert_eq!(
+            flatten(Summation(vec![
+                add(Var, Const(1)),
+                Summation(vec![Const(2), Const(3)])
+            ])),
+            Summation(vec![Var, Const(1), Const(2), Const(3)])
+        );
+        assert_eq!(
+            flatten(mul(Product(vec![Var, Const(2)]), mul(Const(3), Var))),
+            Product(vec![Var, Const(2), Const(3), Var])
+        );
+        // nested sums and products are flattened separately, also below other operators
+        assert_eq!(
+            flatten(sub(
+                add(mul(Var, mul(Var, Var)), add(Var, Const(1))),
+                Const(2)
+            )),
+            sub(
+                Summation(vec![Product(vec![Var, Var, Var]), Var, Const(1)]),
+                Const(2)
+            )
+        );
+        assert_eq!(flatten(div(Var, Const(2))), div(Var, Const(2)));
+
+        let (a, b, c, d) = (Var, mul(Var, Const(2)), Const(3), sub(Var, Const(4)));
+        let expr = add(
+            add(a.clone(), b.clone()),
+            Summation(vec![c.clone(), d.clone()]),
+        );
+        let flattened = flatten(expr.clone());
+        assert_eq!(
+            flattened,
+            Summation(vec![a, Product(vec![Var, Const(2)]), c, d])
+        );
+        for var in [-5, 0, 1, 7] {
+            assert_eq!(eval(&flattened, var), eval(&expr, var));
+        }
+    }
+
+    #[test]
+    fn test_is_constant() {
+        assert!(is_constant(&Const(1)));
+        assert!(!is_constant(&Var));
+        assert!(is_constant(&mul(Const(2), Const(3))));
+        assert!(!is_constant(&add(Var, mul(Const(2), Const(3)))));
+        assert!(is_constant(&sigma(Const(1), Const(3), Var)));
+        assert!(!is_constant(&sigma(Const(1), Var, Const(3))));
+    }
+
+    #[test]
+    fn test_precompile() {
+        assert_eq!(
+            precompile(&add(Var, mul(Const(2), Const(3)))),
+            add(Var, Const(6))
+        );
+        assert_eq!(
+            precompile(&sigma(
+                Const(1),
+                Var,
+                add(Var, sigma(Const(1), Const(3), Var))
+            )),
+            sigma(Const(1), Var, add(Var, Const(6)))
+        );
+        // failing subexpressions are left alone (but their parts may still be folded)
+        let failing = add(Var, div(Const(1), sub(Const(2), Const(2))));
+        assert_eq!(precompile(&failing), add(Var, div(Const(1), Const(0))));
+
+        let expr = mul(
+            sub(Var, Summation(vec![Const(1), Const(2)])),
+            if_then_else(Const(0), Const(5), Const(7)),
+        );
+        let compiled = precompile(&expr);
+        assert_eq!(compiled, mul(sub(Var, Const(3)), Const(7)));
+        for var in -10..10 {
+            assert_eq!(eval(&compiled, var), eval(&expr, var));
+        }
+    }
+
+    #[test]
+    fn test_simplify() {
+        assert_eq!(
+            simplify(&add(Var, mul(Const(2), Const(3)))),
+            add(Var, Const(6))
+        );
+        assert_eq!(
+            simplify(&add(Const(0), mul(Var, sub(Const(2), Const(1))))),
+            Var
+        );
+        assert_eq!(simplify(&div(sub(Var, Const(0)), Const(1))), Var);
+        assert_eq!(
+            simplify(&Summation(vec![Const(0), Var, sub(Const(1), Const(1))])),
+            Var
+        );
+        assert_eq!(
+            simplify(&Summation(vec![Var, Const(0), Var])),
+            Summation(vec![Var, Var])
+        );
+        assert_eq!(simplify(&div(Var, Const(0))), div(Var, Const(0)));
+    }
+
+    #[test]
+    fn test_simplify_if_and_sigma() {
+        // the branch that is never taken is dropped, even though it would fail
+        assert_eq!(
+            simplify(&if_then_else(Const(1), Var, div(Var, Const(0)))),
+            Var
+        );
+        assert_eq!(
+            simplify(&if_then_else(
+                sub(Const(2), Const(2)),
+                Var,
+                mul(Var, Const(1))
+            )),
+            Var
+        );
+        let unknown = if_then_else(Var, Const(1), Const(2));
+        assert_eq!(simplify(&unknown), unknown);
+
+        // a Sigma with constant bounds is folded, unless it fails; in that case small ones are expanded
+        assert_eq!(simplify(&sigma(Const(1), Const(3), Var)), Const(6));
+        assert_eq!(
+            simplify(&sigma(
+                Const(1),
+                Const(3),
+                div(Const(6), sub(Var, Const(2)))
+            )),
+            Summation(vec![Const(-6), div(Const(6), Const(0)), Const(6)])
+        );
+        let large = sigma(Const(1), Const(100), div(Const(6), sub(Var, Const(2))));
+        assert_eq!(simplify(&large), large);
+    }
+
+    #[test]
+    fn test_checked_division() {
+        assert_eq!(checked_div(7, 2), Some(3));
+        assert_eq!(checked_div(-7, 2), Some(-3));
+        assert_eq!(checked_div(7, 0), None);
+        assert_eq!(checked_div(i64::MIN, -1), None);
+        assert_eq!(checked_div(i64::MIN, 1), Some(i64::MIN));
+
+        assert_eq!(checked_rem(7, 2), Some(1));
+        assert_eq!(checked_rem(-7, 2), Some(-1));
+        assert_eq!(checked_rem(7, 0), None);
+        assert_eq!(checked_rem(i64::MIN, -1), Some(0));
+    }
+
+    #[test]
+    fn test_division_overflow() {
+        assert_eq!(eval(&div(Const(i64::MIN), Const(-1)), 0), None);
+        assert_eq!(eval(&div(Var, Const(-1)), i64::MIN), None);
+        assert_eq!(eval(&rem(Const(i64::MIN), Const(-1)), 0), Some(0));
+        assert_eq!(eval(&rem(Var, Const(5)), 17), Some(2));
+        assert_eq!(eval(&rem(Var, Const(0)), 17), None);
+    }
+
+    #[test]
+    fn test_static_div_by_zero() {
+        assert!(has_static_div_by_zero(&div(Var, sub(Const(3), Const(3)))));
+        assert!(has_static_div_by_zero(&rem(Var, Const(0))));
+        assert!(has_static_div_by_zero(&add(Const(1), div(Var, Const(0)))));
+        assert!(has_static_div_by_zero(&if_then_else(
+            Var,
+            Const(1),
+            div(Const(1), Summation(vec![]))
+        )));
+        assert!(!has_static_div_by_zero(&div(Var, Var)));
+        assert!(!has_static_div_by_zero(&div(Var, sub(Var, Var))));
+        assert!(!has_static_div_by_zero(&div(Const(0), Const(3))));
+        assert!(!has_static_div_by_zero(&sigma(
+            Const(1),
+            Const(3),
+            div(Const(1), Var)
+        )));
+    }
+
+    #[test]
+    fn test_from_integers() {
+        assert_eq!(Expr::from(7), Const(7));
+        assert_eq!(Expr::from(7i64), Const(7));
+        assert_eq!(Expr::from(i64::MIN), Const(i64::MIN));
+        assert_eq!(Expr::from(-7i32), Const(-7));
+        assert_eq!(add(Var, 5.into()), add(Var, Const(5)));
+    }
+
+    #[test]
+    fn test_eval_bounded() {
+        let expr = add(mul(Var, Var), Const(1));
+        assert_eq!(eval_bounded(&expr, 3, 3), Ok(10));
+        assert_eq!(eval_bounded(&expr, 3, 2), Err(EvalError::TooDeep));
+        assert_eq!(
+            eval_bounded(&div(Var, Const(0)), 3, 10),
+            Err(EvalError::Undefined)
+        );
+
+        let mut deep = Var;
+        for _ in 0..10_000 {
+            deep = add(deep, Const(1));
+        }
+        assert_eq!(eval_bounded(&deep, 0, 100), Err(EvalError::TooDeep));
     }
 }
 
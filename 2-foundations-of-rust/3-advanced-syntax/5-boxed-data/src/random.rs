@@ -0,0 +1,168 @@
+//! Random expressions, for testing properties that should hold for all expressions (e.g. that simplifying an
+//! expression doesn't change its value).
+
+use rand::Rng;
+
+use crate::*;
+
+// Generates a random expression whose operators are nested at most `max_depth` deep. If `allow_div_by_zero` is
+// false, every divisor is a non-zero constant, so the expression can never fail by dividing by zero; otherwise the
+// divisors are random expressions as well.
+//
+// The bounds of Sigma and SigmaStep are always small constants, to keep the expressions cheap to evaluate. A Call is
+// always of one of the functions from `random_functions`, but not always with the number of arguments it takes.
+pub fn random_expr(rng: &mut impl Rng, max_depth: usize, allow_div_by_zero: bool) -> Expr {
+    if max_depth == 0 || rng.gen_ratio(1, 4) {
+        return if rng.gen() {
+            Var
+        } else {
+            Const(rng.gen_range(-10..=10))
+        };
+    }
+
+    let sub_expr = |rng: &mut _| random_expr(rng, max_depth - 1, allow_div_by_zero);
+    let divisor = |rng: &mut _| {
+        if allow_div_by_zero {
+            random_expr(rng, max_depth - 1, allow_div_by_zero)
+        } else {
+            Const(nonzero(rng))
+        }
+    };
+    match rng.gen_range(0..19) {
+        0 => add(sub_expr(rng), sub_expr(rng)),
+        1 => sub(sub_expr(rng), sub_expr(rng)),
+        2 => mul(sub_expr(rng), sub_expr(rng)),
+        3 => div(sub_expr(rng), divisor(rng)),
+        4 => {
+            let len = rng.gen_range(0..4);
+            Summation((0..len).map(|_| sub_expr(rng)).collect())
+        }
+        5 => {
+            let from = rng.gen_range(-5..=5);
+            let to = from + rng.gen_range(-1..=5);
+            sigma(Const(from), Const(to), sub_expr(rng))
+        }
+        6 => {
+            let from = rng.gen_range(-5..=5);
+            let to = from + rng.gen_range(-5..=5);
+            let step = if allow_div_by_zero {
+                rng.gen_range(-2..=2)
+            } else {
+                nonzero(rng)
+            };
+            sigma_step(Const(from), Const(to), Const(step), sub_expr(rng))
+        }
+        7 => if_then_else(sub_expr(rng), sub_expr(rng), sub_expr(rng)),
+        8 => min(sub_expr(rng), sub_expr(rng)),
+        9 => max(sub_expr(rng), sub_expr(rng)),
+        10 => isqrt(sub_expr(rng)),
+        11 => gcd(sub_expr(rng), sub_expr(rng)),
+        12 => lcm(sub_expr(rng), sub_expr(rng)),
+        13 => rem(sub_expr(rng), divisor(rng)),
+        14 => fact(sub_expr(rng)),
+        15 => sqrt(sub_expr(rng)),
+        16 => clamp(sub_expr(rng), sub_expr(rng), sub_expr(rng)),
+        17 => {
+            let name = if rng.gen() { "neg" } else { "avg" };
+            let len = rng.gen_range(0..=2);
+            Expr::Call(name.to_string(), (0..len).map(|_| sub_expr(rng)).collect())
+        }
+        _ => {
+            let len = rng.gen_range(0..4);
+            Product((0..len).map(|_| sub_expr(rng)).collect())
+        }
+    }
+}
+
+// The functions called by random expressions, to evaluate them with `eval_with_fns`: `neg` negates its one argument,
+// and `avg` is the mean of its two arguments, rounded toward zero.
+pub fn random_functions() -> Functions {
+    let mut fns = Functions::new();
+    fns.insert("neg".to_string(), |args| match args {
+        [a] => a.checked_neg(),
+        _ => None,
+    });
+    fns.insert("avg".to_string(), |args| match args {
+        [a, b] => Some(((*a as i128 + *b as i128) / 2) as i64),
+        _ => None,
+    });
+    fns
+}
+
+fn nonzero(rng: &mut impl Rng) -> i64 {
+    let k = rng.gen_range(1..=10);
+    if rng.gen() {
+        k
+    } else {
+        -k
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn divisors_are_nonzero_constants(expr: &Expr) -> bool {
+        let divisor_ok = match expr {
+            Expr::Div(_, rhs) | Expr::Mod(_, rhs) => matches!(**rhs, Const(k) if k != 0),
+            Expr::SigmaStep(_, _, step, _) => matches!(**step, Const(k) if k != 0),
+            _ => true,
+        };
+        divisor_ok
+            && expr.operator().is_none_or(|(_, operands)| {
+                operands.into_iter().all(divisors_are_nonzero_constants)
+            })
+    }
+
+    #[test]
+    fn test_no_div_by_zero() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..1000 {
+            let expr = random_expr(&mut rng, 5, false);
+            assert!(divisors_are_nonzero_constants(&expr), "{expr:?}");
+        }
+    }
+
+    #[test]
+    fn test_simplify_preserves_eval() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let fns = random_functions();
+        let eval = |expr: &Expr, var| eval_with_fns(expr, var, &fns);
+        let mut checked = 0;
+        for _ in 0..2000 {
+            let expr = random_expr(&mut rng, 5, true);
+            let simplified = simplify(&expr);
+            for var in [-3, 0, 1, 5, 1000] {
+                if let (Some(expected), Some(actual)) = (eval(&expr, var), eval(&simplified, var)) {
+                    assert_eq!(expected, actual, "{expr:?} simplified to {simplified:?}");
+                    checked += 1;
+                }
+            }
+        }
+        // make sure that not (almost) all the expressions fail to evaluate
+        assert!(checked > 1000);
+    }
+
+    #[test]
+    fn test_flatten_preserves_eval() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let fns = random_functions();
+        let eval = |expr: &Expr, var| eval_with_fns(expr, var, &fns);
+        let mut checked = 0;
+        for _ in 0..2000 {
+            let expr = random_expr(&mut rng, 5, true);
+            let flattened = flatten(expr.clone());
+            // regrouping may change whether an intermediate result overflows, but not the value itself
+            for var in [-3, 0, 1, 5, 1000] {
+                if let (Some(expected), Some(actual)) = (eval(&expr, var), eval(&flattened, var)) {
+                    assert_eq!(expected, actual, "{expr:?} flattened to {flattened:?}");
+                    checked += 1;
+                }
+            }
+        }
+        // make sure that not (almost) all the expressions fail to evaluate
+        assert!(checked > 1000);
+    }
+}
@@ -0,0 +1,212 @@
+//! Reverse Polish (postfix) notation for expressions: the operands of an operator are written before it, so e.g.
+//! `add(mul(Var, Const(3)), Const(2))` is written as `x 3 * 2 +`. An operator that can take any number of operands
+//! (`sum` or `product`) is followed by their count, e.g. `x 1 2 sum:3`, and so is a call of a function, which is
+//! marked as such: `x 3 call:max:2` (the name is written with `escape_name`, so that it is a single token).
+
+use crate::{escape_name, tokens, unescape_name, Const, Expr, ParseError, Var};
+
+pub fn to_rpn(expr: &Expr) -> String {
+    let mut out = Vec::new();
+    write_rpn(expr, &mut out);
+    out.join(" ")
+}
+
+fn write_rpn(expr: &Expr, out: &mut Vec<String>) {
+    match expr {
+        Const(k) => out.push(k.to_string()),
+        Var => out.push("x".to_string()),
+        Expr::Call(name, args) => {
+            args.iter().for_each(|e| write_rpn(e, out));
+            out.push(format!("call:{}:{}", escape_name(name), args.len()));
+        }
+        _ => {
+            let (name, operands) = expr.operator().unwrap();
+            let count = operands.len();
+            operands.into_iter().for_each(|e| write_rpn(e, out));
+            if Expr::is_variadic(name) {
+                out.push(format!("{name}:{count}"));
+            } else {
+                out.push(name.to_string());
+            }
+        }
+    }
+}
+
+pub fn from_rpn(s: &str) -> Result<Expr, ParseError> {
+    let mut stack = Vec::new();
+    for (position, token) in tokens(s) {
+        if let Ok(k) = token.parse() {
+            stack.push(Const(k));
+            continue;
+        }
+        if token == "x" {
+            stack.push(Var);
+            continue;
+        }
+
+        if let Some(call) = token.strip_prefix("call:") {
+            let Some((name, count)) = call.rsplit_once(':').and_then(|(name, count)| {
+                Some((unescape_name(name)?, count.parse::<usize>().ok()?))
+            }) else {
+                return Err(ParseError::UnknownToken {
+                    token: token.to_string(),
+                    position,
+                });
+            };
+            if count > stack.len() {
+                return Err(ParseError::MissingOperand {
+                    operator: token.to_string(),
+                    position,
+                });
+            }
+            let args = stack.split_off(stack.len() - count);
+            stack.push(Expr::Call(name, args));
+            continue;
+        }
+
+        let (name, count) = match token.split_once(':') {
+            Some((name, count)) if Expr::is_variadic(name) => (name, count.parse().ok()),
+            _ => (token, Expr::arity(token)),
+        };
+        let Some(count) = count else {
+            return Err(ParseError::UnknownToken {
+                token: token.to_string(),
+                position,
+            });
+        };
+        if count > stack.len() {
+            return Err(ParseError::MissingOperand {
+                operator: token.to_string(),
+                position,
+            });
+        }
+        let operands = stack.split_off(stack.len() - count);
+        stack.push(Expr::from_operator(name, operands).unwrap());
+    }
+
+    let expr = stack.pop().ok_or(ParseError::UnexpectedEnd)?;
+    if !stack.is_empty() {
+        // everything after the first complete expression is superfluous
+        let (position, _) = tokens(s).nth(rpn_len(&stack[0])).unwrap();
+        return Err(ParseError::TrailingInput { position });
+    }
+    Ok(expr)
+}
+
+// the number of tokens needed to write `expr`
+fn rpn_len(expr: &Expr) -> usize {
+    match expr.operator() {
+        None => 1,
+        Some((_, operands)) => 1 + operands.into_iter().map(rpn_len).sum::<usize>(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn test_to_rpn() {
+        assert_eq!(to_rpn(&add(mul(Var, Const(3)), Const(2))), "x 3 * 2 +");
+        assert_eq!(to_rpn(&sub(Const(-1), div(Var, Var))), "-1 x x / -");
+        assert_eq!(
+            to_rpn(&Summation(vec![Var, Const(1), Const(2)])),
+            "x 1 2 sum:3"
+        );
+        assert_eq!(
+            to_rpn(&Expr::Call("max".to_string(), vec![Var, Const(3)])),
+            "x 3 call:max:2"
+        );
+        assert_eq!(
+            to_rpn(&Expr::Call("f(x) %".to_string(), vec![Var])),
+            "x call:f%28x%29%20%25:1"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let exprs = [
+            Const(5),
+            Var,
+            add(mul(Var, Const(3)), Const(2)),
+            sub(Const(-7), sub(Var, Const(5))),
+            div(mul(Var, Var), add(Var, Const(1))),
+            Summation(vec![]),
+            Summation(vec![Var, mul(Const(2), Var), Summation(vec![Const(1)])]),
+            Product(vec![Var, Product(vec![]), Const(3)]),
+            sigma(Const(1), Var, mul(Var, Var)),
+            sigma_step(Const(0), Const(10), Const(2), Var),
+            if_then_else(Var, min(Var, Const(3)), max(Var, Const(-3))),
+            gcd(isqrt(Var), lcm(Var, Const(4))),
+            sqrt(Var),
+            rem(Var, Const(-3)),
+            fact(sub(Var, Const(1))),
+            clamp(Var, Const(0), add(Var, Const(10))),
+            Expr::Call("max".to_string(), vec![Var, add(Var, Const(1))]),
+            Expr::Call("zero".to_string(), vec![]),
+            // function names that are also operator names don't turn into the operator
+            Expr::Call("min".to_string(), vec![Var, Const(1)]),
+            Expr::Call("+".to_string(), vec![Var, Const(1)]),
+            // as do names that aren't a single token by themselves
+            Expr::Call("my fn".to_string(), vec![Var]),
+            Expr::Call("f(x)".to_string(), vec![Var, Const(2)]),
+            Expr::Call("".to_string(), vec![]),
+            Expr::Call("100%\t\u{3000}:2".to_string(), vec![Const(1), Const(2)]),
+        ];
+        for expr in exprs {
+            assert_eq!(from_rpn(&to_rpn(&expr)), Ok(expr));
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            from_rpn("x +"),
+            Err(ParseError::MissingOperand {
+                operator: "+".to_string(),
+                position: 2
+            })
+        );
+        assert_eq!(
+            from_rpn("1 2 sum:3"),
+            Err(ParseError::MissingOperand {
+                operator: "sum:3".to_string(),
+                position: 4
+            })
+        );
+        assert_eq!(
+            from_rpn("x 3 ^"),
+            Err(ParseError::UnknownToken {
+                token: "^".to_string(),
+                position: 4
+            })
+        );
+        assert_eq!(
+            from_rpn("x 3 * 2"),
+            Err(ParseError::TrailingInput { position: 6 })
+        );
+        assert_eq!(
+            from_rpn("x call:max:2"),
+            Err(ParseError::MissingOperand {
+                operator: "call:max:2".to_string(),
+                position: 2
+            })
+        );
+        assert_eq!(
+            from_rpn("x call:max"),
+            Err(ParseError::UnknownToken {
+                token: "call:max".to_string(),
+                position: 2
+            })
+        );
+        assert_eq!(
+            from_rpn("x call:50%:1"),
+            Err(ParseError::UnknownToken {
+                token: "call:50%:1".to_string(),
+                position: 2
+            })
+        );
+        assert_eq!(from_rpn("  "), Err(ParseError::UnexpectedEnd));
+    }
+}
@@ -0,0 +1,257 @@
+//! S-expressions (as in Lisp) for expressions: an operator and its operands are written between parentheses, with
+//! the operator first, so e.g. `add(mul(Var, Const(3)), Const(2))` is written as `(+ (* x 3) 2)`. A call of a function
+//! is written with `call` and the name of the function in place of the operator, e.g. `(call max x 3)`; the name is
+//! written with `escape_name`, so that it is a single token.
+
+use std::iter::Peekable;
+
+use crate::{escape_name, unescape_name, Const, Expr, ParseError, Var};
+
+pub fn to_sexpr(expr: &Expr) -> String {
+    match expr {
+        Const(k) => k.to_string(),
+        Var => "x".to_string(),
+        _ => {
+            let (name, operands) = expr.operator().unwrap();
+            let mut out = match expr {
+                Expr::Call(..) => format!("(call {}", escape_name(name)),
+                _ => format!("({name}"),
+            };
+            for operand in operands {
+                out.push(' ');
+                out.push_str(&to_sexpr(operand));
+            }
+            out.push(')');
+            out
+        }
+    }
+}
+
+pub fn from_sexpr(s: &str) -> Result<Expr, ParseError> {
+    let mut tokens = tokens(s).peekable();
+    let expr = parse(&mut tokens)?;
+    match tokens.next() {
+        None => Ok(expr),
+        Some((position, ")")) => Err(ParseError::UnmatchedParen { position }),
+        Some((position, _)) => Err(ParseError::TrailingInput { position }),
+    }
+}
+
+// splits `s` into parentheses and the whitespace-separated tokens in between, along with their positions
+fn tokens(s: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut rest = s.char_indices().peekable();
+    std::iter::from_fn(move || {
+        while rest.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+        let (start, c) = rest.next()?;
+        let mut end = start + c.len_utf8();
+        if c != '(' && c != ')' {
+            while let Some((i, c)) =
+                rest.next_if(|&(_, c)| !c.is_whitespace() && c != '(' && c != ')')
+            {
+                end = i + c.len_utf8();
+            }
+        }
+        Some((start, &s[start..end]))
+    })
+}
+
+fn parse<'a>(
+    tokens: &mut Peekable<impl Iterator<Item = (usize, &'a str)>>,
+) -> Result<Expr, ParseError> {
+    let (position, token) = tokens.next().ok_or(ParseError::UnexpectedEnd)?;
+    match token {
+        "(" => {}
+        ")" => return Err(ParseError::UnmatchedParen { position }),
+        "x" => return Ok(Var),
+        _ => {
+            return token
+                .parse()
+                .map(Const)
+                .map_err(|_| ParseError::UnknownToken {
+                    token: token.to_string(),
+                    position,
+                })
+        }
+    }
+
+    let open = position;
+    let (position, name) = tokens
+        .next()
+        .ok_or(ParseError::UnmatchedParen { position: open })?;
+    let mut function = None;
+    if name == "call" {
+        function = match tokens.next() {
+            None => return Err(ParseError::UnmatchedParen { position: open }),
+            Some((position, token)) => match unescape_name(token) {
+                Some(function) if token != "(" && token != ")" => Some(function),
+                _ => {
+                    return Err(ParseError::UnknownToken {
+                        token: token.to_string(),
+                        position,
+                    })
+                }
+            },
+        };
+    } else if !Expr::is_variadic(name) && Expr::arity(name).is_none() {
+        return Err(ParseError::UnknownToken {
+            token: name.to_string(),
+            position,
+        });
+    }
+    let mut operands = Vec::new();
+    let mut positions = Vec::new();
+    let close = loop {
+        match tokens.peek() {
+            None => return Err(ParseError::UnmatchedParen { position: open }),
+            Some(&(position, ")")) => {
+                tokens.next();
+                break position;
+            }
+            Some(&(position, _)) => {
+                positions.push(position);
+                operands.push(parse(tokens)?);
+            }
+        }
+    };
+
+    if let Some(function) = function {
+        return Ok(Expr::Call(function, operands));
+    }
+    match Expr::arity(name) {
+        Some(arity) if operands.len() < arity => Err(ParseError::MissingOperand {
+            operator: name.to_string(),
+            position: close,
+        }),
+        Some(arity) if operands.len() > arity => Err(ParseError::TrailingInput {
+            position: positions[arity],
+        }),
+        _ => Ok(Expr::from_operator(name, operands).unwrap()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn test_to_sexpr() {
+        assert_eq!(
+            to_sexpr(&add(mul(Var, Const(3)), Const(2))),
+            "(+ (* x 3) 2)"
+        );
+        assert_eq!(
+            to_sexpr(&Summation(vec![Var, Const(-1), Const(2)])),
+            "(sum x -1 2)"
+        );
+        assert_eq!(to_sexpr(&Summation(vec![])), "(sum)");
+        assert_eq!(
+            to_sexpr(&Expr::Call("max".to_string(), vec![Var, Const(3)])),
+            "(call max x 3)"
+        );
+        assert_eq!(
+            to_sexpr(&Expr::Call("my fn".to_string(), vec![])),
+            "(call my%20fn)"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let exprs = [
+            Const(5),
+            Var,
+            add(mul(Var, Const(3)), Const(2)),
+            sub(Const(-7), sub(Var, Const(5))),
+            div(mul(Var, Var), rem(Var, Const(7))),
+            Summation(vec![]),
+            Summation(vec![Var, mul(Const(2), Var), Summation(vec![Const(1)])]),
+            Product(vec![Var, Product(vec![]), Const(3)]),
+            sigma(Const(1), Var, mul(Var, Var)),
+            sigma_step(Const(0), Const(10), Const(2), Var),
+            if_then_else(Var, min(Var, Const(3)), max(Var, Const(-3))),
+            gcd(isqrt(Var), lcm(Var, Const(4))),
+            sqrt(Var),
+            fact(sub(Var, Const(1))),
+            clamp(Var, Const(0), add(Var, Const(10))),
+            Expr::Call("max".to_string(), vec![Var, add(Var, Const(1))]),
+            Expr::Call("zero".to_string(), vec![]),
+            // function names that are also operator names don't turn into the operator
+            Expr::Call("min".to_string(), vec![Var, Const(1)]),
+            Expr::Call("+".to_string(), vec![Var, Const(1)]),
+            // as do names that aren't a single token by themselves
+            Expr::Call("my fn".to_string(), vec![Var]),
+            Expr::Call("f(x)".to_string(), vec![Var, Const(2)]),
+            Expr::Call("".to_string(), vec![]),
+            Expr::Call("100%\t\u{3000}:2".to_string(), vec![Const(1), Const(2)]),
+        ];
+        for expr in exprs {
+            assert_eq!(from_sexpr(&to_sexpr(&expr)), Ok(expr));
+        }
+        assert_eq!(
+            from_sexpr("  (+(* x 3)\n 2 ) "),
+            Ok(add(mul(Var, Const(3)), Const(2)))
+        );
+    }
+
+    #[test]
+    fn test_unbalanced() {
+        assert_eq!(
+            from_sexpr("(+ (* x 3) 2"),
+            Err(ParseError::UnmatchedParen { position: 0 })
+        );
+        // the innermost parenthesis that isn't closed is reported
+        assert_eq!(
+            from_sexpr("(+ (* x 3"),
+            Err(ParseError::UnmatchedParen { position: 3 })
+        );
+        assert_eq!(
+            from_sexpr("(+ x 3))"),
+            Err(ParseError::UnmatchedParen { position: 7 })
+        );
+        assert_eq!(
+            from_sexpr(")"),
+            Err(ParseError::UnmatchedParen { position: 0 })
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            from_sexpr("(^ x 3)"),
+            Err(ParseError::UnknownToken {
+                token: "^".to_string(),
+                position: 1
+            })
+        );
+        assert_eq!(
+            from_sexpr("(+ x)"),
+            Err(ParseError::MissingOperand {
+                operator: "+".to_string(),
+                position: 4
+            })
+        );
+        assert_eq!(
+            from_sexpr("(+ x 1 2)"),
+            Err(ParseError::TrailingInput { position: 7 })
+        );
+        assert_eq!(
+            from_sexpr("x 1"),
+            Err(ParseError::TrailingInput { position: 2 })
+        );
+        assert_eq!(
+            from_sexpr("(call (max x))"),
+            Err(ParseError::UnknownToken {
+                token: "(".to_string(),
+                position: 6
+            })
+        );
+        assert_eq!(
+            from_sexpr("(call max%2 x)"),
+            Err(ParseError::UnknownToken {
+                token: "max%2".to_string(),
+                position: 6
+            })
+        );
+        assert_eq!(from_sexpr(""), Err(ParseError::UnexpectedEnd));
+    }
+}
@@ -1,10 +1,37 @@
+use std::ops::{Bound, Index, IndexMut, RangeBounds};
+use std::slice::SliceIndex;
+
 /// A growable, generic list that resides on the stack if it's small,
 /// but is moved to the heap to grow larger if needed.
 /// This list is generic over the items it contains as well as the
 /// size of its buffer if it's on the stack.
+#[derive(Debug)]
 pub enum LocalStorageVec<T, const N: usize> {
-    // TODO add some variants containing data
-    // to make the compiler happy
+    /// The items live inline in `buf`; only the first `len` of them are
+    /// part of the list, the rest are default-initialized padding.
+    Stack { buf: [T; N], len: usize },
+    /// The items have outgrown the stack buffer and were moved to the heap.
+    Heap(Vec<T>),
+}
+
+#[cfg(feature = "rayon")]
+mod par;
+
+/// With the `spill-count` feature, the number of times any list (of any
+/// type) moved its items from the stack to the heap, since the last call to
+/// [`take_spill_count`].
+#[cfg(feature = "spill-count")]
+static SPILLS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+/// Returns how many times a list moved its items from the stack to the
+/// heap since the last call, and resets the count; e.g. to check that a
+/// warmed-up hot loop doesn't spill any more. The count is shared by all
+/// lists on all threads. Keeping it costs an atomic increment per spill,
+/// which is cheap next to the allocation a spill makes anyway, but it can
+/// contend if many threads spill at the same time.
+#[cfg(feature = "spill-count")]
+pub fn take_spill_count() -> usize {
+    SPILLS.swap(0, std::sync::atomic::Ordering::Relaxed)
 }
 
 // **Below `From` implementation is used in the tests and are therefore given. However,
@@ -47,6 +74,675 @@ where
     }
 }
 
+impl<T, const N: usize> From<Vec<T>> for LocalStorageVec<T, N> {
+    fn from(v: Vec<T>) -> Self {
+        // The elements are already on the heap, so there's no point in
+        // moving them back onto the stack
+        Self::Heap(v)
+    }
+}
+
+impl<T, const N: usize> AsRef<[T]> for LocalStorageVec<T, N> {
+    fn as_ref(&self) -> &[T] {
+        match self {
+            Self::Stack { buf, len } => &buf[..*len],
+            Self::Heap(v) => v,
+        }
+    }
+}
+
+impl<T, const N: usize> AsMut<[T]> for LocalStorageVec<T, N> {
+    fn as_mut(&mut self) -> &mut [T] {
+        match self {
+            Self::Stack { buf, len } => &mut buf[..*len],
+            Self::Heap(v) => v,
+        }
+    }
+}
+
+// Indexing works like it does for slices, with a single index as well as any
+// kind of range, and only ever reaches the items, never the padding.
+impl<T, I: SliceIndex<[T]>, const N: usize> Index<I> for LocalStorageVec<T, N> {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        self.as_ref().index(index)
+    }
+}
+
+impl<T, I: SliceIndex<[T]>, const N: usize> IndexMut<I> for LocalStorageVec<T, N> {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        self.as_mut().index_mut(index)
+    }
+}
+
+/// Shows the items like a slice does with `{:?}`, but using their `Display`
+/// implementation: e.g. `[1, 2, 3]`. Where the items reside doesn't matter.
+impl<T: std::fmt::Display, const N: usize> std::fmt::Display for LocalStorageVec<T, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[")?;
+        for (i, item) in self.as_ref().iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{item}")?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl<T, const N: usize> LocalStorageVec<T, N> {
+    /// The number of items in the list
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Stack { len, .. } => *len,
+            Self::Heap(v) => v.len(),
+        }
+    }
+
+    /// Whether the list contains no items
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sorts the items by the key extracted by `f`, preserving the order
+    /// of items with equal keys. See [`slice::sort_by_key`].
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, f: F) {
+        self.as_mut().sort_by_key(f)
+    }
+
+    /// Sorts the items by the key extracted by `f`, without preserving the
+    /// order of items with equal keys. See [`slice::sort_unstable_by_key`].
+    pub fn sort_unstable_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, f: F) {
+        self.as_mut().sort_unstable_by_key(f)
+    }
+
+    /// Divides the items into two slices at `mid`: the first holds the
+    /// items before `mid`, the second the rest. Panics if `mid > len`. See
+    /// [`slice::split_at`].
+    pub fn split_at(&self, mid: usize) -> (&[T], &[T]) {
+        self.as_ref().split_at(mid)
+    }
+
+    /// Like [`LocalStorageVec::split_at`], but the two slices can be
+    /// mutated independently. See [`slice::split_at_mut`].
+    pub fn split_at_mut(&mut self, mid: usize) -> (&mut [T], &mut [T]) {
+        self.as_mut().split_at_mut(mid)
+    }
+
+    /// Returns the first item and a slice of the rest, or `None` if the
+    /// list is empty. See [`slice::split_first`].
+    pub fn split_first(&self) -> Option<(&T, &[T])> {
+        self.as_ref().split_first()
+    }
+
+    /// Returns the last item and a slice of the rest, or `None` if the list
+    /// is empty. See [`slice::split_last`].
+    pub fn split_last(&self) -> Option<(&T, &[T])> {
+        self.as_ref().split_last()
+    }
+
+    /// Groups the items into arrays of `C` items, from the front, plus a
+    /// slice of the fewer than `C` remaining ones. Panics if `C` is 0. See
+    /// [`slice::as_chunks`].
+    pub fn as_chunks<const C: usize>(&self) -> (&[[T; C]], &[T]) {
+        self.as_ref().as_chunks()
+    }
+
+    /// Returns an iterator over the items from back to front, i.e. starting
+    /// with the most recently pushed one
+    pub fn rev_iter(&self) -> impl Iterator<Item = &T> {
+        self.as_ref().iter().rev()
+    }
+
+    /// Whether the list contains an item equal to `x`
+    pub fn contains(&self, x: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.as_ref().contains(x)
+    }
+
+    /// Returns the index of the first item that satisfies `pred`, or `None`
+    /// if there is none
+    pub fn position<P: FnMut(&T) -> bool>(&self, pred: P) -> Option<usize> {
+        self.as_ref().iter().position(pred)
+    }
+
+    /// Returns the index of the last item that satisfies `pred`, or `None`
+    /// if there is none
+    pub fn rposition<P: FnMut(&T) -> bool>(&self, pred: P) -> Option<usize> {
+        self.as_ref().iter().rposition(pred)
+    }
+
+    /// Applies `f` to every item in place; the list keeps its length and
+    /// stays where it is (on the stack or on the heap)
+    pub fn map_in_place<F: FnMut(&mut T)>(&mut self, f: F) {
+        self.as_mut().iter_mut().for_each(f)
+    }
+
+    /// Reorders the items so that the ones that satisfy `pred` come first,
+    /// keeping the relative order within both groups, and returns how many
+    /// satisfy it. This doesn't allocate, but takes quadratic time in the
+    /// worst case.
+    pub fn partition_in_place<F: FnMut(&T) -> bool>(&mut self, mut pred: F) -> usize {
+        let items = self.as_mut();
+        let mut count = 0;
+        for i in 0..items.len() {
+            if pred(&items[i]) {
+                // move it in front of the items that don't satisfy `pred`
+                items[count..=i].rotate_right(1);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Returns a pointer to the first item, e.g. to pass the items to C
+    /// code. The pointer is valid for reading `len` items, as long as the
+    /// list is not modified; anything that may move the items to the heap
+    /// or reallocate them (such as `push`), or moves the list itself while
+    /// it's on the stack, invalidates it.
+    pub fn as_ptr(&self) -> *const T {
+        self.as_ref().as_ptr()
+    }
+
+    /// Like [`LocalStorageVec::as_ptr`], but the pointer is valid for
+    /// writing as well
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.as_mut().as_mut_ptr()
+    }
+
+    /// Consumes the list and leaks its items, returning a mutable slice of
+    /// them that lives as long as the caller wants. Items on the stack are
+    /// moved to the heap first. See [`Vec::leak`].
+    pub fn leak<'a>(self) -> &'a mut [T] {
+        match self {
+            Self::Heap(v) => v.leak(),
+            stack => stack.into_iter().collect::<Vec<_>>().leak(),
+        }
+    }
+
+    /// Consumes the list and returns its items as a boxed slice without any
+    /// spare capacity. Items on the stack are moved into a new allocation of
+    /// exactly `len` items. See [`Vec::into_boxed_slice`].
+    pub fn into_boxed_slice(self) -> Box<[T]> {
+        match self {
+            Self::Heap(v) => v.into_boxed_slice(),
+            stack => stack.into_iter().collect(),
+        }
+    }
+
+    /// Returns how many bytes the list takes up as `(stack, heap)`: the
+    /// size of the list itself (which includes the stack buffer, even when
+    /// it's unused), and the size of the heap allocation, if any
+    pub fn memory_footprint(&self) -> (usize, usize) {
+        let heap = match self {
+            Self::Stack { .. } => 0,
+            Self::Heap(v) => v.capacity() * std::mem::size_of::<T>(),
+        };
+        (std::mem::size_of::<Self>(), heap)
+    }
+}
+
+impl<T: Default, const N: usize> LocalStorageVec<T, N> {
+    /// Creates an empty list that resides on the stack
+    pub fn new() -> Self {
+        Self::Stack {
+            buf: [(); N].map(|_| T::default()),
+            len: 0,
+        }
+    }
+
+    /// Moves the items of `vec` onto the stack, unlike `From<Vec<T>>`, which
+    /// keeps them on the heap. If they don't fit, `vec` is handed back
+    /// unchanged as the error.
+    pub fn try_from_vec(vec: Vec<T>) -> Result<Self, Vec<T>> {
+        if vec.len() > N {
+            return Err(vec);
+        }
+        let mut list = Self::Heap(vec);
+        list.shrink_to_stack();
+        Ok(list)
+    }
+
+    /// Appends `value` to the end of the list, moving the list to the heap
+    /// if the stack buffer is full
+    pub fn push(&mut self, value: T) {
+        match self {
+            Self::Stack { buf, len } if *len < N => {
+                buf[*len] = value;
+                *len += 1;
+            }
+            _ => self.spill(1).push(value),
+        }
+    }
+
+    /// Appends all items of `array`, moving the list to the heap in one go
+    /// if they don't all fit in the stack buffer
+    pub fn extend_from_array<const M: usize>(&mut self, array: [T; M]) {
+        match self {
+            Self::Stack { buf, len } if *len + M <= N => {
+                buf[*len..]
+                    .iter_mut()
+                    .zip(array)
+                    .for_each(|(slot, item)| *slot = item);
+                *len += M;
+            }
+            _ => self.spill(M).extend(array),
+        }
+    }
+
+    /// Removes the last item of the list and returns it, or `None` if the
+    /// list is empty
+    pub fn pop(&mut self) -> Option<T> {
+        match self {
+            Self::Stack { len: 0, .. } => None,
+            Self::Stack { buf, len } => {
+                *len -= 1;
+                Some(std::mem::take(&mut buf[*len]))
+            }
+            Self::Heap(v) => v.pop(),
+        }
+    }
+
+    /// Inserts `value` at position `index`, shifting all items after it to
+    /// the right. Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, value: T) {
+        assert!(index <= self.len(), "insertion index out of bounds");
+        match self {
+            Self::Stack { buf, len } if *len < N => {
+                buf[*len] = value;
+                buf[index..=*len].rotate_right(1);
+                *len += 1;
+            }
+            _ => self.spill(1).insert(index, value),
+        }
+    }
+
+    /// Inserts clones of all of `values` at position `index`, shifting all
+    /// items after it to the right, and moving the list to the heap in one
+    /// go if they don't all fit. Panics if `index > len`.
+    pub fn insert_slice(&mut self, index: usize, values: &[T])
+    where
+        T: Clone,
+    {
+        assert!(index <= self.len(), "insertion index out of bounds");
+        let count = values.len();
+        match self {
+            Self::Stack { buf, len } if *len + count <= N => {
+                buf[*len..*len + count].clone_from_slice(values);
+                buf[index..*len + count].rotate_right(count);
+                *len += count;
+            }
+            _ => {
+                self.spill(count)
+                    .splice(index..index, values.iter().cloned());
+            }
+        }
+    }
+
+    /// Inserts `value` at the front of the list, shifting all items to the
+    /// right; this is the same as `insert(0, value)`
+    pub fn prepend(&mut self, value: T) {
+        self.insert(0, value);
+    }
+
+    /// Removes and returns the item at position `index`, shifting all items
+    /// after it to the left. Panics if `index >= len`.
+    pub fn remove(&mut self, index: usize) -> T {
+        assert!(index < self.len(), "removal index out of bounds");
+        match self {
+            Self::Stack { buf, len } => {
+                buf[index..*len].rotate_left(1);
+                *len -= 1;
+                std::mem::take(&mut buf[*len])
+            }
+            Self::Heap(v) => v.remove(index),
+        }
+    }
+
+    /// Removes the items in `range`, shifting all items after it to the
+    /// left. Panics if the range is decreasing or ends past `len`. Unlike
+    /// [`Vec::drain`], the removed items are dropped right away.
+    pub fn remove_range<R: RangeBounds<usize>>(&mut self, range: R) {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start
+                .checked_add(1)
+                .expect("removal range start overflows usize"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end
+                .checked_add(1)
+                .expect("removal range end overflows usize"),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        assert!(start <= end, "removal range is decreasing");
+        assert!(end <= self.len(), "removal range out of bounds");
+        match self {
+            Self::Stack { buf, len } => {
+                buf[start..*len].rotate_left(end - start);
+                *len -= end - start;
+                buf[*len..].iter_mut().for_each(|item| *item = T::default());
+            }
+            Self::Heap(v) => {
+                v.drain(start..end);
+            }
+        }
+    }
+
+    /// Removes all items from the list
+    pub fn clear(&mut self) {
+        match self {
+            Self::Stack { buf, len } => {
+                buf[..*len].iter_mut().for_each(|item| *item = T::default());
+                *len = 0;
+            }
+            Self::Heap(v) => v.clear(),
+        }
+    }
+
+    /// Shortens the list to its first `new_len` items, dropping the rest;
+    /// this does nothing if the list isn't longer than that. The list stays
+    /// where it is, even if it would fit on the stack again. See
+    /// [`Vec::truncate`].
+    pub fn truncate(&mut self, new_len: usize) {
+        match self {
+            Self::Stack { buf, len } => {
+                if new_len < *len {
+                    buf[new_len..*len]
+                        .iter_mut()
+                        .for_each(|item| *item = T::default());
+                    *len = new_len;
+                }
+            }
+            Self::Heap(v) => v.truncate(new_len),
+        }
+    }
+
+    /// Shortens the list like [`LocalStorageVec::truncate`], and moves it
+    /// back to the stack if it's on the heap but fits on the stack now
+    pub fn truncate_and_compact(&mut self, new_len: usize) {
+        self.truncate(new_len);
+        self.shrink_to_stack();
+    }
+
+    /// Keeps only the items for which `f` returns true, preserving their
+    /// order; the freed slots of the stack buffer are reset to their default.
+    /// See [`Vec::retain`].
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        match self {
+            Self::Stack { buf, len } => {
+                let mut kept = 0;
+                for i in 0..*len {
+                    if f(&buf[i]) {
+                        buf.swap(kept, i);
+                        kept += 1;
+                    }
+                }
+                buf[kept..*len]
+                    .iter_mut()
+                    .for_each(|item| *item = T::default());
+                *len = kept;
+            }
+            Self::Heap(v) => v.retain(f),
+        }
+    }
+
+    /// Filters the list like [`LocalStorageVec::retain`], and moves it back
+    /// to the stack if it's on the heap but fits on the stack now
+    pub fn retain_compact<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        self.retain(f);
+        self.shrink_to_stack();
+    }
+
+    /// Removes all but the first of consecutive items for which
+    /// `same_bucket` returns true; it is passed the item to remove or keep,
+    /// and the previous item that was kept. The freed slots of the stack
+    /// buffer are reset to their default. See [`Vec::dedup_by`].
+    pub fn dedup_by<F: FnMut(&mut T, &mut T) -> bool>(&mut self, mut same_bucket: F) {
+        match self {
+            Self::Stack { buf, len } => {
+                if *len == 0 {
+                    return;
+                }
+                // `buf[..kept]` holds the items that were kept so far
+                let mut kept = 1;
+                for i in 1..*len {
+                    let (done, rest) = buf.split_at_mut(i);
+                    if !same_bucket(&mut rest[0], &mut done[kept - 1]) {
+                        buf.swap(kept, i);
+                        kept += 1;
+                    }
+                }
+                buf[kept..*len]
+                    .iter_mut()
+                    .for_each(|item| *item = T::default());
+                *len = kept;
+            }
+            Self::Heap(v) => v.dedup_by(same_bucket),
+        }
+    }
+
+    /// Removes all but the first of consecutive items whose keys, as
+    /// extracted by `key`, are equal; the freed slots of the stack buffer
+    /// are reset to their default. See [`Vec::dedup_by_key`].
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&mut T) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b))
+    }
+
+    /// Removes all but the first of consecutive equal items; the freed slots
+    /// of the stack buffer are reset to their default. See [`Vec::dedup`].
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b)
+    }
+
+    /// Removes consecutive equal items like [`LocalStorageVec::dedup`], and
+    /// returns the removed items in their original order
+    pub fn dedup_returning(&mut self) -> Vec<T>
+    where
+        T: PartialEq,
+    {
+        let mut removed = Vec::new();
+        self.dedup_by(|a, b| {
+            let same = a == b;
+            if same {
+                removed.push(std::mem::take(a));
+            }
+            same
+        });
+        removed
+    }
+
+    /// Removes the items for which `f` returns true and returns an iterator
+    /// over them, in their original order; the remaining items keep their
+    /// order too. Unlike the (unstable) [`Vec::extract_if`], the items are
+    /// removed right away, even if the iterator is dropped without being
+    /// consumed.
+    pub fn extract_if<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) -> IntoIter<T, N> {
+        let mut extracted = Self::new();
+        match self {
+            Self::Stack { buf, len } => {
+                let mut kept = 0;
+                for i in 0..*len {
+                    if f(&mut buf[i]) {
+                        extracted.push(std::mem::take(&mut buf[i]));
+                    } else {
+                        buf.swap(kept, i);
+                        kept += 1;
+                    }
+                }
+                // every slot in `buf[kept..len]` was either taken, or swapped
+                // with one that was, so they're all default already
+                *len = kept;
+            }
+            Self::Heap(v) => v.retain_mut(|item| {
+                if f(item) {
+                    extracted.push(std::mem::take(item));
+                    false
+                } else {
+                    true
+                }
+            }),
+        }
+        extracted.into_iter()
+    }
+
+    /// Consumes the list, splitting it into the items that satisfy `f` and
+    /// those that don't. Both lists keep their relative order and reside on
+    /// the stack if they fit.
+    pub fn partition<F: FnMut(&T) -> bool>(self, mut f: F) -> (Self, Self) {
+        let mut matching = Self::new();
+        let mut non_matching = Self::new();
+        for item in self {
+            if f(&item) {
+                matching.push(item);
+            } else {
+                non_matching.push(item);
+            }
+        }
+        (matching, non_matching)
+    }
+
+    /// Moves the items back to the stack if they're on the heap and both
+    /// their number and `min_capacity` fit in the stack buffer; otherwise
+    /// shrinks the heap allocation to hold at least `min_capacity` items.
+    /// See [`Vec::shrink_to`].
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        match self {
+            Self::Heap(v) if v.len() <= N && min_capacity <= N => self.shrink_to_stack(),
+            Self::Heap(v) => v.shrink_to(min_capacity),
+            Self::Stack { .. } => {}
+        }
+    }
+
+    /// Overwrites the items of `target` with clones of the items of this
+    /// list. If `target` is on the heap, it stays there and keeps its
+    /// allocation (which only grows if it's too small); if it's on the
+    /// stack, it only moves to the heap if the items don't fit.
+    pub fn clone_into(&self, target: &mut Self)
+    where
+        T: Clone,
+    {
+        let items = self.as_ref();
+        target.clear();
+        match target {
+            Self::Stack { buf, len } if items.len() <= N => {
+                buf[..items.len()].clone_from_slice(items);
+                *len = items.len();
+            }
+            _ => target.spill(items.len()).extend_from_slice(items),
+        }
+    }
+
+    /// Consumes the list and returns an iterator over its items in chunks
+    /// of `size`, each of which is a list of its own that resides on the
+    /// stack if it fits; the last chunk is shorter if `size` doesn't divide
+    /// the length. Panics if `size` is 0. See [`slice::chunks`].
+    pub fn into_chunks(self, size: usize) -> impl Iterator<Item = Self> {
+        assert!(size > 0, "chunk size must be non-zero");
+        let mut items = self.into_iter();
+        std::iter::from_fn(move || {
+            let mut chunk = Self::new();
+            items.by_ref().take(size).for_each(|item| chunk.push(item));
+            (!chunk.is_empty()).then_some(chunk)
+        })
+    }
+
+    /// Moves the items back to the stack if they're on the heap but fit in
+    /// the stack buffer; this frees the heap allocation
+    fn shrink_to_stack(&mut self) {
+        if let Self::Heap(v) = self {
+            if v.len() <= N {
+                let len = v.len();
+                let mut items = std::mem::take(v).into_iter();
+                *self = Self::Stack {
+                    buf: [(); N].map(|_| items.next().unwrap_or_default()),
+                    len,
+                };
+            }
+        }
+    }
+
+    /// Moves the items to the heap if they are still on the stack, reserving
+    /// room for at least `additional` more, and returns the heap `Vec`
+    fn spill(&mut self, additional: usize) -> &mut Vec<T> {
+        if let Self::Stack { buf, len } = self {
+            let mut v = Vec::with_capacity(*len + additional);
+            v.extend(buf[..*len].iter_mut().map(std::mem::take));
+            *self = Self::Heap(v);
+            #[cfg(feature = "spill-count")]
+            SPILLS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+        match self {
+            Self::Heap(v) => v,
+            Self::Stack { .. } => unreachable!(),
+        }
+    }
+}
+
+impl<T: Default, const N: usize> Default for LocalStorageVec<T, N> {
+    /// Creates an empty list that resides on the stack, like
+    /// [`LocalStorageVec::new`]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const N: usize> IntoIterator for LocalStorageVec<T, N> {
+    type Item = T;
+    type IntoIter = IntoIter<T, N>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let inner = match self {
+            // The padding after `len` is never yielded
+            Self::Stack { buf, len } => IntoIterInner::Stack(buf.into_iter().take(len)),
+            Self::Heap(v) => IntoIterInner::Heap(v.into_iter()),
+        };
+        IntoIter { inner }
+    }
+}
+
+/// An iterator that moves the items out of a [`LocalStorageVec`]
+pub struct IntoIter<T, const N: usize> {
+    inner: IntoIterInner<T, N>,
+}
+
+enum IntoIterInner<T, const N: usize> {
+    Stack(std::iter::Take<std::array::IntoIter<T, N>>),
+    Heap(std::vec::IntoIter<T>),
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match &mut self.inner {
+            IntoIterInner::Stack(it) => it.next(),
+            IntoIterInner::Heap(it) => it.next(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.inner {
+            IntoIterInner::Stack(it) => it.size_hint(),
+            IntoIterInner::Heap(it) => it.size_hint(),
+        }
+    }
+}
+
+// Both inner iterators know exactly how many items are left, and keep
+// returning `None` once they're exhausted
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> std::iter::FusedIterator for IntoIter<T, N> {}
+
 #[cfg(test)]
 mod test {
     use crate::LocalStorageVec;
@@ -54,7 +750,7 @@ mod test {
     #[test]
     // Don't remove the #[ignore] attribute or your tests will take forever!
     #[ignore = "This test is just to validate the definition of `LocalStorageVec`. If it compiles, all is OK"]
-    #[allow(unreachable_code, unused_variables)]
+    #[allow(unreachable_code, unused_variables, clippy::empty_loop)]
     fn it_compiles() {
         // Here's a trick to 'initialize' a type while not actually
         // creating a value: an infinite `loop` expression diverges
@@ -77,178 +773,184 @@ mod test {
         }
     }
 
-    // Uncomment me for part B
-    // #[test]
-    // fn it_from_vecs() {
-    //     // The `vec!` macro creates a `Vec<T>` in a way that resembles
-    //     // array-initialization syntax.
-    //     let vec: LocalStorageVec<usize, 10> = LocalStorageVec::from(vec![1, 2, 3]);
-    //     // Assert that the call to `from` indeed yields a `Heap` variant
-    //     assert!(matches!(vec, LocalStorageVec::Heap(_)));
-    //
-    //     let vec: LocalStorageVec<usize, 2> = LocalStorageVec::from(vec![1, 2, 3]);
-    //
-    //     assert!(matches!(vec, LocalStorageVec::Heap(_)));
-    // }
+    #[test]
+    fn it_from_vecs() {
+        // The `vec!` macro creates a `Vec<T>` in a way that resembles
+        // array-initialization syntax.
+        let vec: LocalStorageVec<usize, 10> = LocalStorageVec::from(vec![1, 2, 3]);
+        // Assert that the call to `from` indeed yields a `Heap` variant
+        assert!(matches!(vec, LocalStorageVec::Heap(_)));
 
-    // Uncomment me for part C
-    // #[test]
-    // fn it_as_refs() {
-    //     let vec: LocalStorageVec<i32, 256> = LocalStorageVec::from([0; 128]);
-    //     let slice: &[i32] = vec.as_ref();
-    //     assert!(slice.len() == 128);
-    //     let vec: LocalStorageVec<i32, 32> = LocalStorageVec::from([0; 128]);
-    //     let slice: &[i32] = vec.as_ref();
-    //     assert!(slice.len() == 128);
-    //
-    //     let mut vec: LocalStorageVec<i32, 256> = LocalStorageVec::from([0; 128]);
-    //     let slice_mut: &[i32] = vec.as_mut();
-    //     assert!(slice_mut.len() == 128);
-    //     let mut vec: LocalStorageVec<i32, 32> = LocalStorageVec::from([0; 128]);
-    //     let slice_mut: &[i32] = vec.as_mut();
-    //     assert!(slice_mut.len() == 128);
-    // }
+        let vec: LocalStorageVec<usize, 2> = LocalStorageVec::from(vec![1, 2, 3]);
 
-    // Uncomment me for part D
-    // #[test]
-    // fn it_constructs() {
-    //     let vec: LocalStorageVec<usize, 10> = LocalStorageVec::new();
-    //     // Assert that the call to `new` indeed yields a `Stack` variant with zero length
-    //     assert!(matches!(vec, LocalStorageVec::Stack { buf: _, len: 0 }));
-    // }
+        assert!(matches!(vec, LocalStorageVec::Heap(_)));
+    }
 
-    // Uncomment me for part D
-    // #[test]
-    // fn it_lens() {
-    //     let vec: LocalStorageVec<_, 3> = LocalStorageVec::from([0, 1, 2]);
-    //     assert_eq!(vec.len(), 3);
-    //     let vec: LocalStorageVec<_, 2> = LocalStorageVec::from([0, 1, 2]);
-    //     assert_eq!(vec.len(), 3);
-    // }
+    #[test]
+    fn it_as_refs() {
+        let vec: LocalStorageVec<i32, 256> = LocalStorageVec::from([0; 128]);
+        let slice: &[i32] = vec.as_ref();
+        assert!(slice.len() == 128);
+        let vec: LocalStorageVec<i32, 32> = LocalStorageVec::from([0; 128]);
+        let slice: &[i32] = vec.as_ref();
+        assert!(slice.len() == 128);
 
-    // Uncomment me for part D
-    // #[test]
-    // fn it_pushes() {
-    //     let mut vec: LocalStorageVec<_, 128> = LocalStorageVec::new();
-    //     for value in 0..128 {
-    //         vec.push(value);
-    //     }
-    //     assert!(matches!(vec, LocalStorageVec::Stack { len: 128, .. }));
-    //     for value in 128..256 {
-    //         vec.push(value);
-    //     }
-    //     assert!(matches!(vec, LocalStorageVec::Heap(v) if v.len() == 256))
-    // }
+        let mut vec: LocalStorageVec<i32, 256> = LocalStorageVec::from([0; 128]);
+        let slice_mut: &[i32] = vec.as_mut();
+        assert!(slice_mut.len() == 128);
+        let mut vec: LocalStorageVec<i32, 32> = LocalStorageVec::from([0; 128]);
+        let slice_mut: &[i32] = vec.as_mut();
+        assert!(slice_mut.len() == 128);
+    }
 
-    // Uncomment me for part D
-    // #[test]
-    // fn it_pops() {
-    //     let mut vec: LocalStorageVec<_, 128> = LocalStorageVec::from([0; 128]);
-    //     for _ in 0..128 {
-    //         assert_eq!(vec.pop(), Some(0))
-    //     }
-    //     assert_eq!(vec.pop(), None);
-    //
-    //     let mut vec: LocalStorageVec<_, 128> = LocalStorageVec::from([0; 256]);
-    //     for _ in 0..256 {
-    //         assert_eq!(vec.pop(), Some(0))
-    //     }
-    //     assert_eq!(vec.pop(), None);
-    //
-    //     let mut vec: LocalStorageVec<_, 128> = LocalStorageVec::from(vec![0; 256]);
-    //     for _ in 0..256 {
-    //         assert_eq!(vec.pop(), Some(0))
-    //     }
-    //     assert_eq!(vec.pop(), None);
-    // }
+    #[test]
+    fn it_constructs() {
+        let vec: LocalStorageVec<usize, 10> = LocalStorageVec::new();
+        // Assert that the call to `new` indeed yields a `Stack` variant with zero length
+        assert!(matches!(vec, LocalStorageVec::Stack { buf: _, len: 0 }));
+    }
 
-    // Uncomment me for part D
-    // #[test]
-    // fn it_inserts() {
-    //     let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from([0, 1, 2]);
-    //     vec.insert(1, 3);
-    //     assert!(matches!(
-    //         vec,
-    //         LocalStorageVec::Stack {
-    //             buf: [0, 3, 1, 2],
-    //             len: 4
-    //         }
-    //     ));
-    //
-    //     let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from([0, 1, 2, 3]);
-    //     vec.insert(1, 3);
-    //     assert!(matches!(vec, LocalStorageVec::Heap { .. }));
-    //     assert_eq!(vec.as_ref(), &[0, 3, 1, 2, 3]);
-    //
-    //     let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from([0, 1, 2, 3, 4]);
-    //     vec.insert(1, 3);
-    //     assert!(matches!(vec, LocalStorageVec::Heap { .. }));
-    //     assert_eq!(vec.as_ref(), &[0, 3, 1, 2, 3, 4])
-    // }
+    #[test]
+    fn it_lens() {
+        let vec: LocalStorageVec<_, 3> = LocalStorageVec::from([0, 1, 2]);
+        assert_eq!(vec.len(), 3);
+        let vec: LocalStorageVec<_, 2> = LocalStorageVec::from([0, 1, 2]);
+        assert_eq!(vec.len(), 3);
+    }
 
-    // Uncomment me for part D
-    // #[test]
-    // fn it_removes() {
-    //     let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from([0, 1, 2]);
-    //     let elem = vec.remove(1);
-    //     dbg!(&vec);
-    //     assert!(matches!(
-    //         vec,
-    //         LocalStorageVec::Stack {
-    //             buf: [0, 2, _, _],
-    //             len: 2
-    //         }
-    //     ));
-    //     assert_eq!(elem, 1);
-    //
-    //     let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from([0, 1, 2]);
-    //     let elem = vec.remove(1);
-    //     assert!(matches!(vec, LocalStorageVec::Heap(..)));
-    //     assert_eq!(vec.as_ref(), &[0, 2]);
-    //     assert_eq!(elem, 1);
-    // }
+    #[test]
+    fn it_pushes() {
+        let mut vec: LocalStorageVec<_, 128> = LocalStorageVec::new();
+        for value in 0..128 {
+            vec.push(value);
+        }
+        assert!(matches!(vec, LocalStorageVec::Stack { len: 128, .. }));
+        for value in 128..256 {
+            vec.push(value);
+        }
+        assert!(matches!(vec, LocalStorageVec::Heap(v) if v.len() == 256))
+    }
 
-    // Uncomment me for part D
-    // #[test]
-    // fn it_clears() {
-    //     let mut vec: LocalStorageVec<_, 10> = LocalStorageVec::from([0, 1, 2, 3]);
-    //     assert!(matches!(vec, LocalStorageVec::Stack { buf: _, len: 4 }));
-    //     vec.clear();
-    //     assert_eq!(vec.len(), 0);
-    //
-    //     let mut vec: LocalStorageVec<_, 3> = LocalStorageVec::from([0, 1, 2, 3]);
-    //     assert!(matches!(vec, LocalStorageVec::Heap(_)));
-    //     vec.clear();
-    //     assert_eq!(vec.len(), 0);
-    // }
+    #[test]
+    fn it_pops() {
+        let mut vec: LocalStorageVec<_, 128> = LocalStorageVec::from([0; 128]);
+        for _ in 0..128 {
+            assert_eq!(vec.pop(), Some(0))
+        }
+        assert_eq!(vec.pop(), None);
 
-    // Uncomment me for part E
-    // #[test]
-    // fn it_iters() {
-    //     let vec: LocalStorageVec<_, 128> = LocalStorageVec::from([0; 32]);
-    //     let mut iter = vec.into_iter();
-    //     for item in &mut iter {
-    //         assert_eq!(item, 0);
-    //     }
-    //     assert_eq!(iter.next(), None);
-    //
-    //     let vec: LocalStorageVec<_, 128> = LocalStorageVec::from(vec![0; 128]);
-    //     let mut iter = vec.into_iter();
-    //     for item in &mut iter {
-    //         assert_eq!(item, 0);
-    //     }
-    //     assert_eq!(iter.next(), None);
-    // }
+        let mut vec: LocalStorageVec<_, 128> = LocalStorageVec::from([0; 256]);
+        for _ in 0..256 {
+            assert_eq!(vec.pop(), Some(0))
+        }
+        assert_eq!(vec.pop(), None);
 
-    // Uncomment me for part F
-    // #[test]
-    // fn it_indexes() {
-    //     let vec: LocalStorageVec<i32, 10> = LocalStorageVec::from([0, 1, 2, 3, 4, 5]);
-    //     assert_eq!(vec[1], 1);
-    //     assert_eq!(vec[..2], [0, 1]);
-    //     assert_eq!(vec[4..], [4, 5]);
-    //     assert_eq!(vec[1..3], [1, 2]);
-    // }
+        let mut vec: LocalStorageVec<_, 128> = LocalStorageVec::from(vec![0; 256]);
+        for _ in 0..256 {
+            assert_eq!(vec.pop(), Some(0))
+        }
+        assert_eq!(vec.pop(), None);
+    }
+
+    #[test]
+    fn it_inserts() {
+        let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from([0, 1, 2]);
+        vec.insert(1, 3);
+        assert!(matches!(
+            vec,
+            LocalStorageVec::Stack {
+                buf: [0, 3, 1, 2],
+                len: 4
+            }
+        ));
+
+        let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from([0, 1, 2, 3]);
+        vec.insert(1, 3);
+        assert!(matches!(vec, LocalStorageVec::Heap { .. }));
+        assert_eq!(vec.as_ref(), &[0, 3, 1, 2, 3]);
+
+        let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from([0, 1, 2, 3, 4]);
+        vec.insert(1, 3);
+        assert!(matches!(vec, LocalStorageVec::Heap { .. }));
+        assert_eq!(vec.as_ref(), &[0, 3, 1, 2, 3, 4])
+    }
+
+    #[test]
+    fn it_removes() {
+        let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from([0, 1, 2]);
+        let elem = vec.remove(1);
+        assert!(matches!(
+            vec,
+            LocalStorageVec::Stack {
+                buf: [0, 2, _, _],
+                len: 2
+            }
+        ));
+        assert_eq!(elem, 1);
+
+        let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from([0, 1, 2]);
+        let elem = vec.remove(1);
+        assert!(matches!(vec, LocalStorageVec::Heap(..)));
+        assert_eq!(vec.as_ref(), &[0, 2]);
+        assert_eq!(elem, 1);
+    }
+
+    #[test]
+    fn it_clears() {
+        let mut vec: LocalStorageVec<_, 10> = LocalStorageVec::from([0, 1, 2, 3]);
+        assert!(matches!(vec, LocalStorageVec::Stack { buf: _, len: 4 }));
+        vec.clear();
+        assert_eq!(vec.len(), 0);
+
+        let mut vec: LocalStorageVec<_, 3> = LocalStorageVec::from([0, 1, 2, 3]);
+        assert!(matches!(vec, LocalStorageVec::Heap(_)));
+        vec.clear();
+        assert_eq!(vec.len(), 0);
+    }
+
+    #[test]
+    fn it_iters() {
+        let vec: LocalStorageVec<_, 128> = LocalStorageVec::from([0; 32]);
+        let mut iter = vec.into_iter();
+        for item in &mut iter {
+            assert_eq!(item, 0);
+        }
+        assert_eq!(iter.next(), None);
+
+        let vec: LocalStorageVec<_, 128> = LocalStorageVec::from(vec![0; 128]);
+        let mut iter = vec.into_iter();
+        for item in &mut iter {
+            assert_eq!(item, 0);
+        }
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn it_indexes() {
+        let vec: LocalStorageVec<i32, 10> = LocalStorageVec::from([0, 1, 2, 3, 4, 5]);
+        assert_eq!(vec[1], 1);
+        assert_eq!(vec[..2], [0, 1]);
+        assert_eq!(vec[4..], [4, 5]);
+        assert_eq!(vec[1..3], [1, 2]);
+    }
+
+    #[test]
+    fn it_indexes_ranges() {
+        let mut vec: LocalStorageVec<_, 8> = LocalStorageVec::from([10, 20, 30, 40]);
+        assert_eq!(&vec[1..3], &[20, 30]);
+        assert_eq!(&vec[..], &[10, 20, 30, 40]);
+        vec[2..].iter_mut().for_each(|item| *item += 1);
+        vec[0] = 0;
+        assert_eq!(vec.as_ref(), &[0, 20, 31, 41]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_panics_indexing_past_len() {
+        let vec: LocalStorageVec<_, 16> = LocalStorageVec::from([10, 20, 30, 40]);
+        // the padding is never reachable, even though the stack buffer is long enough
+        let _ = &vec[1..10];
+    }
 
     // Uncomment me for part H
     // #[test]
@@ -280,4 +982,629 @@ mod test {
     //     let chunks = vec.chunks_mut(4);
     //     let slice: &mut [_] = vec.deref_mut();
     // }
+
+    #[test]
+    fn it_sorts_by_key() {
+        let mut vec: LocalStorageVec<_, 8> = LocalStorageVec::from([(1, 'c'), (2, 'a'), (3, 'b')]);
+        vec.sort_by_key(|&(_, key)| key);
+        assert_eq!(vec.as_ref(), &[(2, 'a'), (3, 'b'), (1, 'c')]);
+
+        let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from([(1, 'c'), (2, 'a'), (3, 'b')]);
+        vec.sort_unstable_by_key(|&(_, key)| key);
+        assert_eq!(vec.as_ref(), &[(2, 'a'), (3, 'b'), (1, 'c')]);
+    }
+
+    #[test]
+    fn it_partitions() {
+        let vec: LocalStorageVec<_, 8> = LocalStorageVec::from([0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        let (evens, odds) = vec.partition(|x| x % 2 == 0);
+        assert!(matches!(evens, LocalStorageVec::Stack { len: 5, .. }));
+        assert!(matches!(odds, LocalStorageVec::Stack { len: 5, .. }));
+        assert_eq!(evens.as_ref(), &[0, 2, 4, 6, 8]);
+        assert_eq!(odds.as_ref(), &[1, 3, 5, 7, 9]);
+
+        let vec: LocalStorageVec<_, 4> = LocalStorageVec::from([0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        let (small, large) = vec.partition(|&x| x < 3);
+        assert!(matches!(small, LocalStorageVec::Stack { len: 3, .. }));
+        assert!(matches!(large, LocalStorageVec::Heap(_)));
+        assert_eq!(small.as_ref(), &[0, 1, 2]);
+        assert_eq!(large.as_ref(), &[3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn it_leaks() {
+        let vec: LocalStorageVec<_, 4> = LocalStorageVec::from([1, 2, 3]);
+        let slice: &'static mut [i32] = vec.leak();
+        assert_eq!(slice, &[1, 2, 3]);
+        slice[0] = 10;
+        assert_eq!(slice, &[10, 2, 3]);
+
+        let vec: LocalStorageVec<_, 2> = LocalStorageVec::from([1, 2, 3]);
+        let slice: &'static mut [i32] = vec.leak();
+        slice.reverse();
+        assert_eq!(slice, &[3, 2, 1]);
+    }
+
+    #[test]
+    fn it_converts_into_boxed_slice() {
+        let vec: LocalStorageVec<String, 4> =
+            LocalStorageVec::from(["a".to_string(), "b".to_string()]);
+        let boxed: Box<[String]> = vec.into_boxed_slice();
+        assert_eq!(&*boxed, ["a", "b"]);
+
+        let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from([1, 2, 3]);
+        vec.pop();
+        assert_eq!(&*vec.into_boxed_slice(), [1, 2]);
+    }
+
+    #[test]
+    fn it_retains() {
+        let mut vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 2, 3, 4, 5, 6]);
+        vec.retain(|&item| item % 3 != 0);
+        assert_eq!(vec.as_ref(), &[1, 2, 4, 5]);
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 4);
+            assert_eq!(buf, [1, 2, 4, 5, 0, 0, 0, 0]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from([1, 2, 3, 4, 5, 6]);
+        vec.retain(|&item| item % 2 == 0);
+        assert_eq!(vec.as_ref(), &[2, 4, 6]);
+    }
+
+    #[test]
+    fn it_dedups_by_key() {
+        let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from([(1, "a"), (1, "b"), (2, "c")]);
+        vec.dedup_by_key(|(id, _)| *id);
+        assert_eq!(vec.as_ref(), &[(1, "a"), (2, "c")]);
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 2);
+            assert_eq!(buf, [(1, "a"), (2, "c"), (0, ""), (0, "")]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from([1, 1, 2, 3, 3, 3, 1]);
+        vec.dedup_by_key(|item| *item);
+        assert_eq!(vec.as_ref(), &[1, 2, 3, 1]);
+
+        let mut vec: LocalStorageVec<i32, 2> = LocalStorageVec::new();
+        vec.dedup_by_key(|item| *item);
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn it_splits_at() {
+        let mut vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 2, 3, 4, 5]);
+        assert_eq!(vec.split_at(2), (&[1, 2][..], &[3, 4, 5][..]));
+        // the padding after the items is never part of the second slice
+        assert_eq!(vec.split_at(5), (&[1, 2, 3, 4, 5][..], &[][..]));
+
+        let (left, right) = vec.split_at_mut(3);
+        left.iter_mut().for_each(|item| *item *= 10);
+        right.reverse();
+        assert_eq!(vec.as_ref(), &[10, 20, 30, 5, 4]);
+
+        let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from([1, 2, 3]);
+        let (left, right) = vec.split_at_mut(1);
+        std::mem::swap(&mut left[0], &mut right[1]);
+        assert_eq!(vec.as_ref(), &[3, 2, 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_panics_splitting_past_len() {
+        let vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 2, 3]);
+        vec.split_at(4);
+    }
+
+    #[test]
+    fn it_iterates_in_reverse() {
+        let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::new();
+        vec.push(1);
+        vec.push(2);
+        vec.push(3);
+        // the padding after the items is never yielded
+        assert_eq!(vec.rev_iter().collect::<Vec<_>>(), vec![&3, &2, &1]);
+
+        vec.push(4);
+        vec.push(5);
+        assert_eq!(
+            vec.rev_iter().copied().collect::<Vec<_>>(),
+            vec![5, 4, 3, 2, 1]
+        );
+        assert_eq!(vec.as_ref(), &[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn it_finds_positions() {
+        let vec: LocalStorageVec<_, 8> = LocalStorageVec::from([3, 1, 4, 1, 5]);
+        assert_eq!(vec.position(|&item| item == 1), Some(1));
+        assert_eq!(vec.rposition(|&item| item == 1), Some(3));
+        assert_eq!(vec.position(|&item| item > 4), Some(4));
+        assert_eq!(vec.rposition(|&item| item > 4), Some(4));
+        // the padding is never searched, even though it matches
+        assert_eq!(vec.position(|&item| item == 0), None);
+        assert_eq!(vec.rposition(|&item| item == 0), None);
+
+        let vec: LocalStorageVec<_, 2> = LocalStorageVec::from([3, 1, 4, 1, 5]);
+        assert_eq!(vec.rposition(|&item| item < 4), Some(3));
+    }
+
+    #[test]
+    fn it_maps_in_place() {
+        let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from([1, 2, 3]);
+        vec.map_in_place(|x| *x *= 2);
+        // the padding is left alone
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 3);
+            assert_eq!(buf, [2, 4, 6, 0]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from([-5, 0, 5]);
+        vec.map_in_place(|x| *x = (*x).clamp(-1, 1));
+        assert_eq!(vec.as_ref(), &[-1, 0, 1]);
+    }
+
+    #[test]
+    fn it_extracts_if() {
+        let mut vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 2, 3, 4, 5, 6]);
+        let extracted: Vec<_> = vec.extract_if(|item| *item % 2 == 0).collect();
+        assert_eq!(extracted, vec![2, 4, 6]);
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 3);
+            assert_eq!(buf, [1, 3, 5, 0, 0, 0, 0, 0]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from([1, 2, 3, 4, 5, 6]);
+        // the items are removed even if they're never yielded
+        drop(vec.extract_if(|item| {
+            *item *= 10;
+            *item > 30
+        }));
+        assert_eq!(vec.as_ref(), &[10, 20, 30]);
+    }
+
+    #[test]
+    fn it_into_iters_exact_size() {
+        let vec: LocalStorageVec<_, 4> = LocalStorageVec::from([1, 2, 3]);
+        let mut iter = vec.into_iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.len(), 2);
+        iter.by_ref().for_each(drop);
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+
+        let vec: LocalStorageVec<_, 2> = LocalStorageVec::from([1, 2, 3]);
+        let mut iter = vec.into_iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.nth(2), Some(3));
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn it_defaults_to_empty() {
+        let vec = LocalStorageVec::<u8, 4>::default();
+        assert!(matches!(vec, LocalStorageVec::Stack { len: 0, .. }));
+
+        #[derive(Default)]
+        struct Record {
+            tags: LocalStorageVec<String, 2>,
+        }
+        assert!(Record::default().tags.is_empty());
+    }
+
+    #[test]
+    fn it_splits_into_chunks() {
+        let vec: LocalStorageVec<_, 4> = (0..10).collect::<Vec<_>>().into();
+        let chunks: Vec<_> = vec.into_chunks(4).collect();
+        assert_eq!(
+            chunks.iter().map(LocalStorageVec::len).collect::<Vec<_>>(),
+            vec![4, 4, 2]
+        );
+        assert_eq!(chunks[1].as_ref(), &[4, 5, 6, 7]);
+        assert_eq!(chunks[2].as_ref(), &[8, 9]);
+        assert!(matches!(chunks[0], LocalStorageVec::Stack { .. }));
+
+        // chunks that don't fit on the stack are moved to the heap
+        let vec: LocalStorageVec<_, 2> = LocalStorageVec::from([1, 2, 3]);
+        let chunks: Vec<_> = vec.into_chunks(3).collect();
+        assert_eq!(chunks.len(), 1);
+        assert!(matches!(chunks[0], LocalStorageVec::Heap(_)));
+
+        let vec: LocalStorageVec<i32, 2> = LocalStorageVec::new();
+        assert_eq!(vec.into_chunks(3).count(), 0);
+    }
+
+    #[test]
+    fn it_displays() {
+        let vec: LocalStorageVec<_, 4> = LocalStorageVec::from(["a", "b c"]);
+        // unlike with Debug, the strings are not quoted
+        assert_eq!(vec.to_string(), "[a, b c]");
+        let vec: LocalStorageVec<_, 1> = LocalStorageVec::from(["a", "b c"]);
+        assert_eq!(vec.to_string(), "[a, b c]");
+        assert_eq!(LocalStorageVec::<u8, 2>::new().to_string(), "[]");
+    }
+
+    #[test]
+    fn it_partitions_in_place() {
+        let mut vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 2, 3, 4]);
+        assert_eq!(vec.partition_in_place(|item| item % 2 == 0), 2);
+        assert_eq!(vec.as_ref(), &[2, 4, 1, 3]);
+        assert_eq!(vec.len(), 4);
+
+        let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from([5, 8, 1, 9, 2, 7]);
+        assert_eq!(vec.partition_in_place(|&item| item > 4), 4);
+        assert_eq!(vec.as_ref(), &[5, 8, 9, 7, 1, 2]);
+        assert_eq!(vec.partition_in_place(|_| false), 0);
+    }
+
+    #[test]
+    fn it_exposes_raw_pointers() {
+        let mut vec: LocalStorageVec<u8, 4> = LocalStorageVec::from([7, 8]);
+        // SAFETY: the list holds two items, and isn't modified in the meantime
+        assert_eq!(unsafe { *vec.as_ptr() }, 7);
+        unsafe { *vec.as_mut_ptr().add(1) = 9 };
+        assert_eq!(vec.as_ref(), &[7, 9]);
+
+        let vec: LocalStorageVec<u8, 1> = LocalStorageVec::from([7, 8]);
+        // SAFETY: as above
+        assert_eq!(unsafe { *vec.as_ptr().add(1) }, 8);
+    }
+
+    #[test]
+    fn it_finds_items() {
+        let stack: LocalStorageVec<_, 8> = LocalStorageVec::from([3, 1, 4]);
+        let heap: LocalStorageVec<_, 2> = LocalStorageVec::from([3, 1, 4]);
+        assert!(matches!(stack, LocalStorageVec::Stack { .. }));
+        assert!(matches!(heap, LocalStorageVec::Heap(_)));
+        assert!(stack.contains(&4));
+        assert!(heap.contains(&4));
+        // the padding of the stack buffer doesn't count
+        assert!(!stack.contains(&0));
+        assert!(!heap.contains(&0));
+        assert_eq!(stack.position(|&item| item == 1), Some(1));
+        assert_eq!(heap.position(|&item| item == 1), Some(1));
+        assert_eq!(stack.position(|&item| item == 0), None);
+        assert_eq!(heap.position(|&item| item == 0), None);
+    }
+
+    #[test]
+    fn it_truncates() {
+        let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from([1, 2, 3]);
+        vec.truncate(5);
+        assert_eq!(vec.as_ref(), &[1, 2, 3]);
+        vec.truncate(1);
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 1);
+            assert_eq!(buf, [1, 0, 0, 0]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        let mut vec: LocalStorageVec<_, 8> = (0..20).collect::<Vec<_>>().into();
+        vec.truncate(5);
+        assert!(matches!(vec, LocalStorageVec::Heap(_)));
+        assert_eq!(vec.len(), 5);
+    }
+
+    #[test]
+    fn it_truncates_and_compacts() {
+        let mut vec: LocalStorageVec<_, 8> = (0..20).collect::<Vec<_>>().into();
+        vec.truncate_and_compact(10);
+        assert!(matches!(vec, LocalStorageVec::Heap(_)));
+        vec.truncate_and_compact(5);
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 5);
+            assert_eq!(buf, [0, 1, 2, 3, 4, 0, 0, 0]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+    }
+
+    #[test]
+    fn it_tries_to_move_a_vec_onto_the_stack() {
+        let vec = LocalStorageVec::<_, 4>::try_from_vec(vec![1, 2, 3]).unwrap();
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 3);
+            assert_eq!(buf, [1, 2, 3, 0]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        let oversized = vec![1, 2, 3, 4, 5];
+        let ptr = oversized.as_ptr();
+        let err = LocalStorageVec::<_, 4>::try_from_vec(oversized).unwrap_err();
+        assert_eq!(err, vec![1, 2, 3, 4, 5]);
+        // it's still the same allocation
+        assert_eq!(err.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn it_dedups() {
+        let mut vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 1, 2, 2, 2, 3]);
+        vec.dedup();
+        assert_eq!(vec.as_ref(), &[1, 2, 3]);
+
+        let mut vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 1, 2, 2, 2, 3]);
+        assert_eq!(vec.dedup_returning(), vec![1, 2, 2]);
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 3);
+            assert_eq!(buf, [1, 2, 3, 0, 0, 0, 0, 0]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from(["a", "a", "b", "a"]);
+        assert_eq!(vec.dedup_returning(), vec!["a"]);
+        assert_eq!(vec.as_ref(), &["a", "b", "a"]);
+    }
+
+    #[test]
+    fn it_extends_from_arrays() {
+        let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from([1, 2]);
+        vec.extend_from_array([3]);
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 3);
+            assert_eq!(buf, [1, 2, 3, 0]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        vec.extend_from_array([4, 5, 6, 7]);
+        if let LocalStorageVec::Heap(v) = vec {
+            assert_eq!(v, [1, 2, 3, 4, 5, 6, 7]);
+            // room for all of them was reserved when spilling
+            assert!(v.capacity() >= 7);
+        } else {
+            panic!("Expected a Heap variant");
+        }
+    }
+
+    #[test]
+    fn it_splits_off_first_and_last() {
+        let vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 2, 3]);
+        // the padding after the items is never part of the rest
+        assert_eq!(vec.split_first(), Some((&1, &[2, 3][..])));
+        assert_eq!(vec.split_last(), Some((&3, &[1, 2][..])));
+
+        let vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1]);
+        assert_eq!(vec.split_first(), Some((&1, &[][..])));
+        assert_eq!(vec.split_last(), Some((&1, &[][..])));
+
+        let vec: LocalStorageVec<i32, 8> = LocalStorageVec::new();
+        assert_eq!(vec.split_first(), None);
+        assert_eq!(vec.split_last(), None);
+
+        let vec: LocalStorageVec<_, 2> = LocalStorageVec::from([1, 2, 3]);
+        assert_eq!(vec.split_first(), Some((&1, &[2, 3][..])));
+        assert_eq!(vec.split_last(), Some((&3, &[1, 2][..])));
+
+        // the rest is a plain slice, so it can be taken apart further the same way
+        fn sum(items: &[i32]) -> i32 {
+            items
+                .split_first()
+                .map_or(0, |(first, rest)| first + sum(rest))
+        }
+        let (first, rest) = vec.split_first().unwrap();
+        assert_eq!(first + sum(rest), 6);
+    }
+
+    #[test]
+    fn it_shrinks_to() {
+        let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from(vec![1, 2]);
+        vec.shrink_to(0);
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 2);
+            assert_eq!(buf, [1, 2, 0, 0]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        // asking for more room than the stack buffer has keeps the items on the heap
+        let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from(Vec::with_capacity(100));
+        vec.extend_from_array([1, 2]);
+        vec.shrink_to(10);
+        if let LocalStorageVec::Heap(v) = vec {
+            assert_eq!(v, [1, 2]);
+            assert!((10..100).contains(&v.capacity()));
+        } else {
+            panic!("Expected a Heap variant");
+        }
+    }
+
+    #[test]
+    fn it_inserts_slices() {
+        let mut vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 2, 3]);
+        vec.insert_slice(1, &[10, 20, 30]);
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 6);
+            assert_eq!(buf, [1, 10, 20, 30, 2, 3, 0, 0]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        let mut vec: LocalStorageVec<_, 4> = LocalStorageVec::from([1, 2, 3]);
+        vec.insert_slice(3, &[]);
+        vec.insert_slice(0, &[-1, 0]);
+        if let LocalStorageVec::Heap(v) = vec {
+            assert_eq!(v, [-1, 0, 1, 2, 3]);
+        } else {
+            panic!("Expected a Heap variant");
+        }
+
+        // the items after `index` keep their order when the list is already on the heap
+        let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from(["a", "b", "c", "d"]);
+        vec.insert_slice(2, &["x", "y", "z"]);
+        assert_eq!(vec.as_ref(), &["a", "b", "x", "y", "z", "c", "d"]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_panics_inserting_a_slice_past_len() {
+        let mut vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 2, 3]);
+        vec.insert_slice(4, &[4]);
+    }
+
+    #[test]
+    fn it_removes_ranges() {
+        let mut vec: LocalStorageVec<_, 6> = LocalStorageVec::from([10, 20, 30, 40]);
+        vec.remove_range(1..3);
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 2);
+            assert_eq!(buf, [10, 40, 0, 0, 0, 0]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from([1, 2, 3, 4, 5]);
+        vec.remove_range(..=1);
+        assert_eq!(vec.as_ref(), &[3, 4, 5]);
+        vec.remove_range(1..);
+        assert_eq!(vec.as_ref(), &[3]);
+        vec.remove_range(1..1);
+        assert_eq!(vec.as_ref(), &[3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_panics_removing_a_range_past_len() {
+        let mut vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 2, 3]);
+        vec.remove_range(2..4);
+    }
+
+    #[test]
+    #[should_panic(expected = "removal range end overflows usize")]
+    fn it_panics_removing_a_range_up_to_usize_max() {
+        let mut vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 2, 3]);
+        vec.remove_range(..=usize::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "removal range start overflows usize")]
+    fn it_panics_removing_a_range_after_usize_max() {
+        let mut vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 2, 3]);
+        vec.remove_range((
+            std::ops::Bound::Excluded(usize::MAX),
+            std::ops::Bound::Unbounded,
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "spill-count")]
+    fn it_counts_spills() {
+        crate::take_spill_count();
+        let mut vec: LocalStorageVec<_, 2> = LocalStorageVec::from([1, 2]);
+        vec.push(3);
+        vec.push(4);
+        // other tests may spill at the same time, so this is a lower bound
+        assert!(crate::take_spill_count() >= 1);
+        assert!(matches!(vec, LocalStorageVec::Heap(_)));
+    }
+
+    #[test]
+    fn it_retains_and_compacts() {
+        let mut vec: LocalStorageVec<_, 16> = (0..100).collect::<Vec<_>>().into();
+        vec.retain_compact(|item| item % 10 == 0);
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 10);
+            assert_eq!(buf[..len], [0, 10, 20, 30, 40, 50, 60, 70, 80, 90]);
+            assert!(buf[len..].iter().all(|&item| item == 0));
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        let mut vec: LocalStorageVec<_, 16> = (0..100).collect::<Vec<_>>().into();
+        vec.retain_compact(|item| item % 2 == 0);
+        assert!(matches!(vec, LocalStorageVec::Heap(_)));
+        assert_eq!(vec.len(), 50);
+    }
+
+    #[test]
+    fn it_clones_into() {
+        let source: LocalStorageVec<_, 4> = LocalStorageVec::from([1, 2, 3]);
+        let mut target: LocalStorageVec<_, 4> = LocalStorageVec::from(Vec::with_capacity(10));
+        target.extend_from_array([9; 6]);
+        let ptr = target.as_ptr();
+        source.clone_into(&mut target);
+        if let LocalStorageVec::Heap(v) = &target {
+            assert_eq!(v, &[1, 2, 3]);
+            assert_eq!(v.capacity(), 10);
+        } else {
+            panic!("Expected a Heap variant");
+        }
+        // it's still the same allocation
+        assert_eq!(target.as_ptr(), ptr);
+
+        let mut target: LocalStorageVec<_, 4> = LocalStorageVec::from([9; 4]);
+        source.clone_into(&mut target);
+        if let LocalStorageVec::Stack { buf, len } = target {
+            assert_eq!(len, 3);
+            assert_eq!(buf, [1, 2, 3, 0]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        let source: LocalStorageVec<_, 4> = LocalStorageVec::from([1, 2, 3, 4, 5]);
+        let mut target = LocalStorageVec::new();
+        source.clone_into(&mut target);
+        assert!(matches!(target, LocalStorageVec::Heap(_)));
+        assert_eq!(target.as_ref(), &[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn it_groups_into_chunks() {
+        let vec: LocalStorageVec<_, 8> = LocalStorageVec::from([1, 2, 3, 4, 5, 6, 7]);
+        let (chunks, rest) = vec.as_chunks::<2>();
+        assert_eq!(chunks, &[[1, 2], [3, 4], [5, 6]]);
+        // the padding is never part of the remainder
+        assert_eq!(rest, &[7]);
+
+        let vec: LocalStorageVec<_, 2> = LocalStorageVec::from([1, 2, 3, 4, 5, 6]);
+        assert_eq!(vec.as_chunks::<3>(), (&[[1, 2, 3], [4, 5, 6]][..], &[][..]));
+        assert_eq!(vec.as_chunks::<8>(), (&[][..], &[1, 2, 3, 4, 5, 6][..]));
+    }
+
+    #[test]
+    fn it_prepends() {
+        let mut vec: LocalStorageVec<_, 3> = LocalStorageVec::from([2]);
+        vec.prepend(1);
+        if let LocalStorageVec::Stack { buf, len } = vec {
+            assert_eq!(len, 2);
+            assert_eq!(buf, [1, 2, 0]);
+        } else {
+            panic!("Expected a Stack variant");
+        }
+
+        let mut vec: LocalStorageVec<_, 3> = LocalStorageVec::from([1, 2, 3]);
+        vec.prepend(0);
+        if let LocalStorageVec::Heap(v) = vec {
+            assert_eq!(v, [0, 1, 2, 3]);
+        } else {
+            panic!("Expected a Heap variant");
+        }
+    }
+
+    #[test]
+    fn it_reports_its_memory_footprint() {
+        let mut vec: LocalStorageVec<u32, 4> = LocalStorageVec::from([1, 2, 3, 4]);
+        let (stack, heap) = vec.memory_footprint();
+        assert!(stack >= 4 * std::mem::size_of::<u32>());
+        assert_eq!(heap, 0);
+
+        vec.push(5);
+        let (stack_after, heap) = vec.memory_footprint();
+        assert_eq!(stack_after, stack);
+        assert!(heap >= 5 * std::mem::size_of::<u32>());
+    }
 }
@@ -0,0 +1,63 @@
+//! With the `rayon` feature, a list can be processed in parallel with
+//! rayon, e.g. `list.par_iter().map(f).sum()`. This only ever covers the
+//! items of the list, never the padding in the stack buffer. `par_iter` and
+//! `par_iter_mut` come from rayon's blanket impls for types whose
+//! references are [`IntoParallelIterator`]s.
+
+use rayon::prelude::*;
+
+use crate::LocalStorageVec;
+
+impl<T: Send, const N: usize> IntoParallelIterator for LocalStorageVec<T, N> {
+    type Iter = rayon::vec::IntoIter<T>;
+    type Item = T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        let v = match self {
+            Self::Stack { buf, len } => buf.into_iter().take(len).collect(),
+            Self::Heap(v) => v,
+        };
+        v.into_par_iter()
+    }
+}
+
+impl<'a, T: Sync, const N: usize> IntoParallelIterator for &'a LocalStorageVec<T, N> {
+    type Iter = rayon::slice::Iter<'a, T>;
+    type Item = &'a T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.as_ref().par_iter()
+    }
+}
+
+impl<'a, T: Send, const N: usize> IntoParallelIterator for &'a mut LocalStorageVec<T, N> {
+    type Iter = rayon::slice::IterMut<'a, T>;
+    type Item = &'a mut T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.as_mut().par_iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn it_sums_in_parallel() {
+        let vec: LocalStorageVec<u64, 16> = (0..100_000).collect::<Vec<_>>().into();
+        assert!(matches!(vec, LocalStorageVec::Heap(_)));
+        let expected: u64 = vec.as_ref().iter().sum();
+        assert_eq!(vec.par_iter().sum::<u64>(), expected);
+        assert_eq!(vec.into_par_iter().sum::<u64>(), expected);
+    }
+
+    #[test]
+    fn it_skips_the_padding_in_parallel() {
+        let mut vec: LocalStorageVec<u64, 16> = LocalStorageVec::from([1, 2, 3]);
+        vec.par_iter_mut().for_each(|item| *item *= 10);
+        assert_eq!(vec.as_ref(), &[10, 20, 30]);
+        assert_eq!(vec.par_iter().count(), 3);
+        assert_eq!(vec.into_par_iter().collect::<Vec<_>>(), [10, 20, 30]);
+    }
+}